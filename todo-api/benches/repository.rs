@@ -0,0 +1,50 @@
+// todo-api only builds a binary, so the bench compiles the repository sources in by path;
+// whatever the bench does not call, test modules included, is unused here.
+#[path = "../src/repositories.rs"]
+#[allow(dead_code, unused_imports)]
+mod repositories;
+#[path = "../src/util/mod.rs"]
+#[allow(dead_code, unused_imports)]
+mod util;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use tokio::runtime::Runtime;
+
+use repositories::{memory::TodoRepositoryForMemory, CreateTodo, TodoRepository};
+
+const SEEDED: usize = 1_000;
+
+async fn seeded() -> TodoRepositoryForMemory {
+    let repository = TodoRepositoryForMemory::new();
+    for i in 0..SEEDED {
+        repository
+            .create(CreateTodo::new(format!("todo {}", i)))
+            .await
+            .expect("failed seed todo");
+    }
+    repository
+}
+
+fn memory(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let repository = runtime.block_on(seeded());
+    let mut group = c.benchmark_group("memory");
+
+    group.bench_function("create", |b| {
+        b.to_async(&runtime).iter_batched(
+            TodoRepositoryForMemory::new,
+            |empty| async move { empty.create(CreateTodo::new("todo".to_string())).await.unwrap() },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("find", |b| {
+        b.to_async(&runtime).iter(|| async { repository.find(SEEDED as i32 / 2).await.unwrap() })
+    });
+    group.bench_function("all", |b| {
+        b.to_async(&runtime).iter(|| async { repository.all().await.unwrap() })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, memory);
+criterion_main!(benches);
@@ -0,0 +1,3 @@
+ALTER TABLE todos ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid();
+
+CREATE UNIQUE INDEX todos_uuid_key ON todos (uuid);
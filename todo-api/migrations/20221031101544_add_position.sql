@@ -0,0 +1,5 @@
+ALTER TABLE todos ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE todos SET position = ordered.position
+FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS position FROM todos) AS ordered
+WHERE todos.id = ordered.id;
@@ -0,0 +1,11 @@
+-- No foreign key to todos: the history of a deleted todo must stay readable.
+CREATE TABLE audit_log
+(
+    id           SERIAL PRIMARY KEY,
+    todo_id      INTEGER     NOT NULL,
+    action       TEXT        NOT NULL,
+    payload_json JSONB       NOT NULL,
+    at           TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX audit_log_todo_id_idx ON audit_log (todo_id, id);
@@ -0,0 +1,2 @@
+ALTER TABLE todos ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE todos ADD COLUMN archived BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,19 @@
+-- A single-row counter rather than a sequence: `nextval` is visible to readers before the write
+-- commits, so a list could be tagged with a revision whose rows it does not contain yet. The row
+-- lock serialises writers on the bump, which is the price of a tag that never runs ahead.
+CREATE TABLE todos_revision (
+    revision BIGINT NOT NULL
+);
+
+INSERT INTO todos_revision (revision) VALUES (0);
+
+CREATE FUNCTION bump_todos_revision() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE todos_revision SET revision = revision + 1;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER todos_revision_trigger
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON todos
+    FOR EACH STATEMENT EXECUTE FUNCTION bump_todos_revision();
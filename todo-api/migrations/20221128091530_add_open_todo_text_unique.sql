@@ -0,0 +1,9 @@
+-- Open todos must not repeat text; once completed, the same task can be added again.
+-- Open duplicates from before the index keep the oldest row's text; the rest get their id appended
+-- so nothing is lost and the index can be built.
+UPDATE todos
+SET text = text || ' (' || id || ')'
+WHERE completed = false
+    AND id NOT IN (SELECT MIN(id) FROM todos WHERE completed = false GROUP BY text);
+
+CREATE UNIQUE INDEX todos_open_text_key ON todos (text) WHERE completed = false;
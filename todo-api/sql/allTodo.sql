@@ -3,4 +3,5 @@ SELECT
 FROM
     TODOS
 ORDER BY
-    ID DESC
\ No newline at end of file
+    POSITION
+    , ID
\ No newline at end of file
@@ -0,0 +1,6 @@
+SELECT
+    VERSION
+FROM
+    _SQLX_MIGRATIONS
+WHERE
+    SUCCESS
\ No newline at end of file
@@ -0,0 +1,9 @@
+UPDATE
+    TODOS
+SET
+    ARCHIVED = true
+WHERE
+    COMPLETED = true
+    AND NOT ARCHIVED
+    AND UPDATED_AT < $1
+RETURNING *
\ No newline at end of file
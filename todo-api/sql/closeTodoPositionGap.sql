@@ -0,0 +1,6 @@
+UPDATE
+    TODOS
+SET
+    POSITION = POSITION - 1
+WHERE
+    POSITION > $1
\ No newline at end of file
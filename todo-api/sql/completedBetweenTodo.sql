@@ -0,0 +1,12 @@
+-- $1 inclusive, $2 exclusive
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    COMPLETED
+    AND UPDATED_AT >= $1
+    AND UPDATED_AT < $2
+ORDER BY
+    UPDATED_AT
+    , ID
\ No newline at end of file
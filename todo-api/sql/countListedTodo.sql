@@ -0,0 +1,7 @@
+-- what `pageTodo.sql` pages over, so a snapshot's total matches the pages it hands out
+SELECT
+    COUNT(*) AS "count!"
+FROM
+    TODOS
+WHERE
+    NOT ARCHIVED
\ No newline at end of file
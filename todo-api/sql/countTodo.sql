@@ -0,0 +1,6 @@
+SELECT
+    COUNT(*) AS "count!"
+FROM
+    TODOS
+WHERE
+    ($1::BOOLEAN IS NULL OR COMPLETED = $1)
\ No newline at end of file
@@ -1,4 +1,5 @@
 DELETE FROM
     TODOS
 WHERE
-    ID = $1
\ No newline at end of file
+    ID = $1
+RETURNING *
\ No newline at end of file
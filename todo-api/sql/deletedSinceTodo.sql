@@ -0,0 +1,9 @@
+SELECT
+    TODO_ID
+FROM
+    AUDIT_LOG
+WHERE
+    ACTION = 'delete'
+    AND AT > $1
+ORDER BY
+    ID
\ No newline at end of file
@@ -0,0 +1,2 @@
+SELECT
+    EXISTS(SELECT 1 FROM TODOS WHERE ID = $1) AS "exists!"
\ No newline at end of file
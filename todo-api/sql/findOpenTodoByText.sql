@@ -0,0 +1,7 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    TEXT = $1
+    AND NOT COMPLETED
\ No newline at end of file
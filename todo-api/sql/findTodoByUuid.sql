@@ -0,0 +1,6 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    UUID = $1
\ No newline at end of file
@@ -0,0 +1,9 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    TEXT = $1
+ORDER BY
+    POSITION
+    , ID
\ No newline at end of file
@@ -0,0 +1,9 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    UUID = ANY($1)
+ORDER BY
+    POSITION
+    , ID
\ No newline at end of file
@@ -0,0 +1,8 @@
+SELECT
+    *
+FROM
+    AUDIT_LOG
+WHERE
+    TODO_ID = $1
+ORDER BY
+    ID
\ No newline at end of file
@@ -0,0 +1,11 @@
+INSERT INTO TODOS (ID, UUID, TEXT, COMPLETED, POSITION, UPDATED_AT, ARCHIVED) 
+VALUES ($1, $2, $3, $4, $5, $6, $7) 
+ON CONFLICT (ID) DO UPDATE
+SET
+    UUID = EXCLUDED.UUID
+    , TEXT = EXCLUDED.TEXT
+    , COMPLETED = EXCLUDED.COMPLETED
+    , POSITION = EXCLUDED.POSITION
+    , UPDATED_AT = EXCLUDED.UPDATED_AT
+    , ARCHIVED = EXCLUDED.ARCHIVED 
+RETURNING (XMAX = 0) AS "inserted!"
\ No newline at end of file
@@ -0,0 +1,2 @@
+INSERT INTO AUDIT_LOG (TODO_ID, ACTION, PAYLOAD_JSON)
+VALUES ($1, $2, $3)
\ No newline at end of file
@@ -1,3 +1,3 @@
-INSERT INTO TODOS (TEXT, COMPLETED) 
-VALUES ($1, false) 
+INSERT INTO TODOS (UUID, TEXT, COMPLETED, POSITION) 
+VALUES ($1, $2, false, (SELECT COALESCE(MAX(POSITION), 0) + 1 FROM TODOS)) 
 RETURNING *
\ No newline at end of file
@@ -0,0 +1,4 @@
+INSERT INTO TODOS (UUID, TEXT, COMPLETED, POSITION) 
+VALUES ($1, $2, false, (SELECT COALESCE(MAX(POSITION), 0) + 1 FROM TODOS)) 
+ON CONFLICT (TEXT) WHERE COMPLETED = false DO NOTHING
+RETURNING *
\ No newline at end of file
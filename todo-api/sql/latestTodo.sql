@@ -0,0 +1,8 @@
+SELECT
+    *
+FROM
+    TODOS
+ORDER BY
+    UPDATED_AT DESC
+    , ID DESC
+LIMIT 1
\ No newline at end of file
@@ -0,0 +1,4 @@
+SELECT
+    COALESCE(MAX(POSITION), 0) AS "max!"
+FROM
+    TODOS
\ No newline at end of file
@@ -0,0 +1,9 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    UPDATED_AT > $1
+ORDER BY
+    UPDATED_AT
+    , ID
\ No newline at end of file
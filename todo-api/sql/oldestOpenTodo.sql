@@ -0,0 +1,9 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    NOT COMPLETED
+ORDER BY
+    ID
+LIMIT 1
\ No newline at end of file
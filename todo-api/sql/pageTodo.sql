@@ -0,0 +1,12 @@
+-- $3 reverses both keys, so a descending page is exactly an ascending one read backwards
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    NOT ARCHIVED
+ORDER BY
+    CASE WHEN $3 THEN -POSITION ELSE POSITION END
+    , CASE WHEN $3 THEN -ID ELSE ID END
+LIMIT $1
+OFFSET $2
\ No newline at end of file
@@ -0,0 +1,5 @@
+SELECT
+    COUNT(*) FILTER (WHERE COMPLETED) AS "completed!"
+    , COUNT(*) AS "total!"
+FROM
+    TODOS
\ No newline at end of file
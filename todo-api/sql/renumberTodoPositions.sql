@@ -0,0 +1,11 @@
+-- closes gaps and ties left behind by an import; only rows that actually move are returned
+UPDATE
+    TODOS
+SET
+    POSITION = RANKED.POSITION
+FROM
+    (SELECT ID, (ROW_NUMBER() OVER (ORDER BY POSITION, ID))::INTEGER AS POSITION FROM TODOS) AS RANKED
+WHERE
+    TODOS.ID = RANKED.ID
+    AND TODOS.POSITION <> RANKED.POSITION
+RETURNING TODOS.*
\ No newline at end of file
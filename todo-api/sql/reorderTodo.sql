@@ -0,0 +1,13 @@
+-- $1: id, $2: current position, $3: new position
+UPDATE
+    TODOS
+SET
+    POSITION = CASE
+        WHEN ID = $1 THEN $3::INTEGER
+        WHEN $3::INTEGER < $2::INTEGER THEN POSITION + 1
+        ELSE POSITION - 1
+    END
+WHERE
+    ID = $1
+    OR POSITION BETWEEN LEAST($2::INTEGER, $3::INTEGER) AND GREATEST($2::INTEGER, $3::INTEGER)
+RETURNING *
\ No newline at end of file
@@ -0,0 +1,8 @@
+UPDATE
+    TODOS
+SET
+    TEXT = REPLACE(TEXT, $1, $2)
+    , UPDATED_AT = NOW()
+WHERE
+    STRPOS(TEXT, $1) > 0
+RETURNING *
\ No newline at end of file
@@ -0,0 +1,7 @@
+SELECT
+    *
+FROM
+    TODOS
+ORDER BY
+    RANDOM()
+LIMIT $1
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- rows already in the requested state are left alone so their age keeps counting toward archiving
+UPDATE
+    TODOS
+SET
+    COMPLETED = $1
+    , ARCHIVED = ARCHIVED AND $1
+    , UPDATED_AT = NOW()
+WHERE
+    COMPLETED <> $1
+RETURNING *
\ No newline at end of file
@@ -0,0 +1,10 @@
+-- $1, $2: ids, $3, $4: their current positions
+UPDATE
+    TODOS
+SET
+    POSITION = CASE
+        WHEN ID = $1 THEN $4::INTEGER
+        ELSE $3::INTEGER
+    END
+WHERE
+    ID IN ($1, $2)
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- keep serial ids ahead of explicitly imported ones
+SELECT
+    SETVAL('todos_id_seq', GREATEST((SELECT MAX(ID) FROM TODOS), (SELECT LAST_VALUE FROM todos_id_seq)))
\ No newline at end of file
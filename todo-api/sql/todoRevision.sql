@@ -0,0 +1,4 @@
+SELECT
+    REVISION AS "revision!"
+FROM
+    TODOS_REVISION
\ No newline at end of file
@@ -0,0 +1,10 @@
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    NOT COMPLETED
+ORDER BY
+    POSITION
+    , ID
+LIMIT $1
\ No newline at end of file
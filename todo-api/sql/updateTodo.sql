@@ -1,8 +1,11 @@
+-- reopening a todo takes it back out of the archive, or it would vanish from every listing
 UPDATE
     TODOS
 SET
-    TEXT = $1
-    , COMPLETED = $2
+    TEXT = COALESCE($1, TEXT)
+    , COMPLETED = COALESCE($2, COMPLETED)
+    , ARCHIVED = ARCHIVED AND COALESCE($2, COMPLETED)
+    , UPDATED_AT = NOW()
 WHERE
     ID = $3
 RETURNING *
\ No newline at end of file
@@ -0,0 +1,9 @@
+INSERT INTO TODOS (UUID, TEXT, COMPLETED, POSITION)
+VALUES ($1, $2, $3, (SELECT COALESCE(MAX(POSITION), 0) + 1 FROM TODOS))
+ON CONFLICT (UUID) DO UPDATE
+SET
+    TEXT = EXCLUDED.TEXT
+    , COMPLETED = EXCLUDED.COMPLETED
+    , ARCHIVED = TODOS.ARCHIVED AND EXCLUDED.COMPLETED
+    , UPDATED_AT = NOW()
+RETURNING *, (XMAX = 0) AS "inserted!"
\ No newline at end of file
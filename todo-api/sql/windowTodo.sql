@@ -0,0 +1,10 @@
+-- $1, $2: lowest and highest id, inclusive
+SELECT
+    *
+FROM
+    TODOS
+WHERE
+    ID BETWEEN $1 AND $2
+    AND NOT ARCHIVED
+ORDER BY
+    ID
\ No newline at end of file
@@ -0,0 +1,142 @@
+use chrono::Utc;
+use std::{future::Future, time::Duration};
+
+use crate::maintenance::ReadOnlyMode;
+use crate::repositories::TodoRepository;
+use crate::webhook::{TodoEvent, Webhooks};
+
+// Sweeps todos completed more than `max_age` ago into the archive every `interval` until
+// `shutdown` resolves. Ticks are skipped while `read_only` is on, since archiving is a write.
+pub async fn run<T, F>(
+    repository: T,
+    webhooks: Webhooks,
+    read_only: ReadOnlyMode,
+    interval: Duration,
+    max_age: chrono::Duration,
+    shutdown: F,
+) where
+    T: TodoRepository,
+    F: Future<Output = ()>,
+{
+    let mut ticker = tokio::time::interval(interval);
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = ticker.tick() => {}
+        }
+        if read_only.is_enabled() {
+            tracing::debug!("auto-archive skipped: read-only mode");
+            continue;
+        }
+        match repository.archive_completed(Utc::now() - max_age).await {
+            Ok(archived) if archived.is_empty() => {}
+            Ok(archived) => {
+                tracing::info!("auto-archived {} completed todos", archived.len());
+                for todo in archived {
+                    webhooks.notify(TodoEvent::Updated { todo });
+                }
+            }
+            Err(e) => tracing::error!("auto-archive failed: {:#}", e),
+        }
+    }
+    tracing::debug!("auto-archive stopped");
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repositories::{test_utils::TodoRepositoryForMemory, SortOrder, Todo};
+    use crate::webhook;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn archives_old_completed_todos_until_shutdown() {
+        let repository = TodoRepositoryForMemory::new();
+        let now = Utc::now();
+        let todo = |id: i32, completed: bool, days_ago: i64| Todo {
+            completed,
+            position: id,
+            updated_at: now - chrono::Duration::days(days_ago),
+            ..Todo::new(id, format!("todo {}", id))
+        };
+        repository
+            .import(vec![todo(1, true, 40), todo(2, true, 1), todo(3, false, 40)])
+            .await
+            .unwrap();
+
+        let (webhook_url, mut received) = webhook::test_utils::receiver(0);
+        let (webhooks, _) = Webhooks::from_url(Some(&webhook_url)).unwrap();
+        let (tx, rx) = oneshot::channel::<()>();
+        let job = tokio::spawn(run(
+            repository.clone(),
+            webhooks,
+            ReadOnlyMode::default(),
+            Duration::from_millis(10),
+            chrono::Duration::days(30),
+            async {
+                rx.await.ok();
+            },
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), job)
+            .await
+            .expect("job did not stop on shutdown")
+            .unwrap();
+
+        let archived = repository
+            .all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.archived)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![true, false, false], archived);
+        let page = repository.page(10, 0, SortOrder::Asc).await.unwrap();
+        assert_eq!(vec![2, 3], page.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+
+        let body = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("webhook did not fire")
+            .unwrap();
+        assert_eq!("updated", body["event"]);
+        assert_eq!(true, body["todo"]["archived"]);
+    }
+
+    #[tokio::test]
+    async fn skips_ticks_while_read_only() {
+        let repository = TodoRepositoryForMemory::new();
+        repository
+            .import(vec![Todo {
+                completed: true,
+                position: 1,
+                updated_at: Utc::now() - chrono::Duration::days(40),
+                ..Todo::new(1, "todo 1".to_string())
+            }])
+            .await
+            .unwrap();
+        let archived = || async { repository.all().await.unwrap()[0].archived };
+
+        let read_only = ReadOnlyMode::new(true);
+        let (tx, rx) = oneshot::channel::<()>();
+        let job = tokio::spawn(run(
+            repository.clone(),
+            Webhooks::default(),
+            read_only.clone(),
+            Duration::from_millis(10),
+            chrono::Duration::days(30),
+            async {
+                rx.await.ok();
+            },
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!archived().await);
+
+        read_only.set(false);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(archived().await);
+        tx.send(()).unwrap();
+        job.await.unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use validator::Validate;
+
+use crate::repositories::reject_control_chars;
+use crate::util::text_len;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct Draft {
+    #[validate(custom = "text_len::validate")]
+    #[validate(custom = "reject_control_chars")]
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct DraftEntry {
+    draft: Draft,
+    saved_at: Instant,
+}
+
+// Auto-saved drafts live only in process memory so keystrokes never reach the database.
+#[derive(Debug, Clone)]
+pub struct DraftStore {
+    store: Arc<RwLock<HashMap<i32, DraftEntry>>>,
+    ttl: Duration,
+}
+
+impl DraftStore {
+    pub fn new(ttl: Duration) -> Self {
+        DraftStore {
+            store: Arc::default(),
+            ttl,
+        }
+    }
+
+    pub fn save(&self, id: i32, draft: Draft) {
+        let mut store = self.store.write().unwrap();
+        let ttl = self.ttl;
+        store.retain(|_, entry| entry.saved_at.elapsed() < ttl);
+        store.insert(
+            id,
+            DraftEntry {
+                draft,
+                saved_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn get(&self, id: i32) -> Option<Draft> {
+        let store = self.store.read().unwrap();
+        store
+            .get(&id)
+            .filter(|entry| entry.saved_at.elapsed() < self.ttl)
+            .map(|entry| entry.draft.clone())
+    }
+
+    pub fn take(&self, id: i32) -> Option<Draft> {
+        let mut store = self.store.write().unwrap();
+        store
+            .remove(&id)
+            .filter(|entry| entry.saved_at.elapsed() < self.ttl)
+            .map(|entry| entry.draft)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn draft(text: &str) -> Draft {
+        Draft {
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn draft_scenario() {
+        let drafts = DraftStore::new(Duration::from_secs(60));
+        assert_eq!(None, drafts.get(1));
+
+        drafts.save(1, draft("first"));
+        drafts.save(1, draft("second"));
+        assert_eq!(Some(draft("second")), drafts.get(1));
+
+        assert_eq!(Some(draft("second")), drafts.take(1));
+        assert_eq!(None, drafts.get(1));
+    }
+
+    #[test]
+    fn draft_expires_after_ttl() {
+        let drafts = DraftStore::new(Duration::from_millis(10));
+        drafts.save(1, draft("text"));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(None, drafts.get(1));
+        assert_eq!(None, drafts.take(1));
+    }
+}
@@ -1,64 +1,789 @@
 use axum::{
     async_trait,
-    extract::{Extension, FromRequest, Path, RequestParts},
-    http::StatusCode,
-    response::IntoResponse,
+    body::StreamBody,
+    extract::{rejection::JsonRejection, Extension, FromRequest, Path, RequestParts},
+    http::{header, StatusCode},
+    response::{Headers, IntoResponse, Response},
     BoxError, Json,
 };
-use serde::de::DeserializeOwned;
-use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{any::Any, convert::Infallible, error::Error, sync::Arc};
+use uuid::Uuid;
 use validator::Validate;
 
-use crate::repositories::{CreateTodo, TodoRepository, UpdateTodo};
+use crate::repositories::{CreateTodo, CreatedIfAbsent, ExportBundle, PatchOperation, Reordered, ReorderTodo, ReplaceText, RepositoryError, SortOrder, SwapTodos, Todo, TodoBoard, TodoFilter, TodoRepository, TodoSync, UpdateTodo, Upserted, UpsertTodo, WordStats};
+use crate::drafts::{Draft, DraftStore};
+use crate::share::ShareSigner;
+use crate::webhook::{TodoEvent, Webhooks};
+use crate::util::{config::Config, json_style::Verbatim, text_len};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateQuery {
+    #[serde(default)]
+    if_absent: bool,
+}
 
 pub async fn create_todo<T: TodoRepository>(
+    QueryParams(query): QueryParams<CreateQuery>,
     ValidatedJson(payload): ValidatedJson<CreateTodo>,
     Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let todo = repository
-        .create(payload)
-        .await
-        .or(Err(StatusCode::NOT_FOUND))?;
+    // `?if_absent=true` makes a replayed create answer 200 with the open todo it matches.
+    if query.if_absent {
+        return match repository.create_if_absent(payload).await.map_err(error_status)? {
+            CreatedIfAbsent::Created(todo) => {
+                webhooks.notify(TodoEvent::Created { todo: todo.clone() });
+                Ok((StatusCode::CREATED, Json(todo)))
+            }
+            CreatedIfAbsent::Existing(todo) => Ok((StatusCode::OK, Json(todo))),
+        };
+    }
+    match repository.create(payload).await {
+        Ok(todo) => {
+            webhooks.notify(TodoEvent::Created { todo: todo.clone() });
+            Ok((StatusCode::CREATED, Json(todo)))
+        }
+        Err(e) => match e.downcast_ref::<RepositoryError>() {
+            Some(RepositoryError::Duplicate(uuid)) => {
+                let todo = repository
+                    .find_by_uuid(*uuid)
+                    .await
+                    .ok()
+                    .flatten()
+                    .ok_or(StatusCode::CONFLICT)?;
+                Ok((StatusCode::CONFLICT, Json(todo)))
+            }
+            _ => Err(error_status(e)),
+        },
+    }
+}
 
-    Ok((StatusCode::CREATED, Json(todo)))
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct UuidLookup {
+    #[validate(length(max = 1000, message = "At most 1000 uuids per lookup."))]
+    uuids: Vec<Uuid>,
+}
+
+// Unknown uuids are left out rather than reported, so whatever is missing from the reply needs re-pushing.
+pub async fn find_many_by_uuid_todo<T: TodoRepository>(
+    ValidatedJson(payload): ValidatedJson<UuidLookup>,
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todos = repository.find_many_by_uuid(&payload.uuids).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(todos)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TextQuery {
+    text: String,
+}
+
+// Byte-for-byte equality: no case folding or trimming, unlike what a search would do.
+pub async fn find_by_text_todo<T: TodoRepository>(
+    QueryParams(query): QueryParams<TextQuery>,
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todos = repository.find_by_text(&query.text).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(todos)))
 }
 
 pub async fn find_todo<T: TodoRepository>(
     Path(id): Path<i32>,
+    QueryParams(fields): QueryParams<FieldsQuery>,
+    format: Format,
     Extension(repository): Extension<Arc<T>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let todo = repository.find(id).await.or(Err(StatusCode::NOT_FOUND))?;
-    Ok((StatusCode::OK, Json(todo)))
+    let todo = repository.find(id).await.map_err(error_status)?;
+    let todo = fields.select(todo)?;
+    Ok((StatusCode::OK, format.encode(todo)))
+}
+
+pub async fn exists_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    Extension(repository): Extension<Arc<T>>,
+) -> StatusCode {
+    match repository.exists(id).await {
+        Ok(true) => StatusCode::OK,
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => error_status(e),
+    }
 }
 
 pub async fn all_todo<T: TodoRepository>(
+    ValidatedQuery(pagination): ValidatedQuery<Pagination>,
+    QueryParams(fields): QueryParams<FieldsQuery>,
+    QueryParams(consistency): QueryParams<ConsistencyQuery>,
+    format: Format,
+    IfNoneMatch(if_none_match): IfNoneMatch,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(config): Extension<Arc<Config>>,
+) -> Result<Response, StatusCode> {
+    let (limit, offset, order) = (pagination.limit(&config), pagination.offset(), pagination.order(&config));
+    if consistency.consistent {
+        let snapshot = repository.snapshot(limit, offset, order).await.map_err(error_status)?;
+        let etag = format.etag(snapshot.revision);
+        if if_none_match.as_deref() == Some(etag.as_str()) {
+            return Ok((StatusCode::NOT_MODIFIED, Headers([(header::ETAG, etag)])).into_response());
+        }
+        let headers = Headers([(header::ETAG.as_str(), etag), (ConsistencyQuery::TOTAL_COUNT, snapshot.count.to_string())]);
+        let todos = fields.select(snapshot.todos)?;
+        return Ok((StatusCode::OK, headers, format.encode(todos)).into_response());
+    }
+    // The revision only moves when a write commits, so reading it before the page means the body
+    // is at least as new as the tag; the worst a concurrent write does is force the next refetch.
+    let revision = repository.revision().await.map_err(error_status)?;
+    let etag = format.etag(revision);
+    if if_none_match.as_deref() == Some(etag.as_str()) {
+        return Ok((StatusCode::NOT_MODIFIED, Headers([(header::ETAG, etag)])).into_response());
+    }
+    let todo = repository
+        .page(limit, offset, order)
+        .await
+        .map_err(error_status)?;
+    let todo = fields.select(todo)?;
+    Ok((StatusCode::OK, Headers([(header::ETAG, etag)]), format.encode(todo)).into_response())
+}
+
+// The longest-neglected open todo, as a nudge.
+pub async fn oldest_todo<T: TodoRepository>(
     Extension(repository): Extension<Arc<T>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let todo = repository.all().await.unwrap();
-    Ok((StatusCode::OK, Json(todo)))
+    let todo = repository.oldest_open().await.map_err(error_status)?;
+    Ok(match todo {
+        Some(todo) => (StatusCode::OK, Json(todo)).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+pub async fn latest_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todo = repository.latest().await.map_err(error_status)?;
+    Ok(match todo {
+        Some(todo) => (StatusCode::OK, Json(todo)).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SyncQuery {
+    since: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompletedQuery {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+}
+
+// `from` is inclusive and `to` exclusive, so consecutive report periods never count a todo twice.
+pub async fn completed_todo<T: TodoRepository>(
+    QueryParams(query): QueryParams<CompletedQuery>,
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, Response> {
+    if query.from >= query.to {
+        return Err(query_rejection("invalid query parameter 'to': must be after 'from'".into()));
+    }
+    let todos = repository
+        .completed_between(query.from, query.to)
+        .await
+        .map_err(|e| error_status(e).into_response())?;
+    Ok((StatusCode::OK, Json(todos)))
+}
+
+pub async fn sync_todo<T: TodoRepository>(
+    ValidatedQuery(query): ValidatedQuery<SyncQuery>,
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // Tombstones are read second so a delete racing the first read still reaches the client.
+    let todos = repository.modified_since(query.since).await.map_err(error_status)?;
+    let deleted = repository.deleted_since(query.since).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(TodoSync { todos, deleted })))
+}
+
+pub async fn count_todo<T: TodoRepository>(
+    ValidatedQuery(filter): ValidatedQuery<TodoFilter>,
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let count = repository.count_where(filter).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "count": count }))))
+}
+
+pub async fn progress_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let progress = repository.progress().await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(progress)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct WindowQuery {
+    id: i32,
+    #[validate(range(min = 0, message = "Can not be negative."))]
+    radius: Option<i64>,
+}
+
+impl WindowQuery {
+    pub const DEFAULT_RADIUS: i64 = 10;
+}
+
+// Lets a client that jumps to one todo render the ones around it; the id need not exist.
+pub async fn window_todo<T: TodoRepository>(
+    ValidatedQuery(query): ValidatedQuery<WindowQuery>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(config): Extension<Arc<Config>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let radius = query.radius.unwrap_or(WindowQuery::DEFAULT_RADIUS).min(config.max_page_size / 2);
+    let todos = repository.window(query.id, radius).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(todos)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct TopQuery {
+    #[validate(range(min = 0, message = "Can not be negative."))]
+    n: Option<i64>,
+}
+
+impl TopQuery {
+    pub const DEFAULT_N: i64 = 5;
+}
+
+pub async fn top_todo<T: TodoRepository>(
+    ValidatedQuery(query): ValidatedQuery<TopQuery>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(config): Extension<Arc<Config>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let n = query.n.unwrap_or(TopQuery::DEFAULT_N).min(config.max_page_size);
+    let todos = repository.top_open(n).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(todos)))
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SampleQuery {
+    #[validate(range(min = 0, message = "Can not be negative."))]
+    n: Option<i64>,
+}
+
+impl SampleQuery {
+    pub const DEFAULT_N: i64 = 10;
+}
+
+// Random todos for filling UI mock-ups with realistic data.
+pub async fn sample_todo<T: TodoRepository>(
+    ValidatedQuery(query): ValidatedQuery<SampleQuery>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(config): Extension<Arc<Config>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let n = query.n.unwrap_or(SampleQuery::DEFAULT_N).min(config.max_page_size);
+    let todos = repository.sample(n).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(todos)))
+}
+
+pub async fn word_stats_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todos = repository.all().await.map_err(error_status)?;
+    let stats = WordStats::new(todos.iter().map(|todo| todo.text.as_str()));
+    Ok((StatusCode::OK, Json(stats)))
+}
+
+pub async fn board_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todos = repository.all().await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(TodoBoard::new(todos))))
+}
+
+// Exports are heavy to build, so clients may reuse one briefly; `private` keeps shared caches out.
+fn export_cache_control(config: &Config) -> String {
+    format!("private, max-age={}", config.export_cache_max_age_secs)
+}
+
+pub async fn export_todo_ndjson<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+    Extension(config): Extension<Arc<Config>>,
+) -> impl IntoResponse {
+    let lines = repository.stream_all().and_then(|todo| async move {
+        let mut line = serde_json::to_vec(&todo)?;
+        line.push(b'\n');
+        Ok(line)
+    });
+
+    (
+        Headers([
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (header::CACHE_CONTROL, export_cache_control(&config)),
+        ]),
+        StreamBody::new(lines),
+    )
+}
+
+pub async fn export_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+    Extension(config): Extension<Arc<Config>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todos = repository.all().await.map_err(error_status)?;
+    let cache_control = Headers([(header::CACHE_CONTROL, export_cache_control(&config))]);
+    let mut res = (StatusCode::OK, cache_control, Json(ExportBundle::new(todos))).into_response();
+    // a bundle is only useful if `/todos/import` takes it back, so the response style stays out of it
+    res.extensions_mut().insert(Verbatim);
+    Ok(res)
+}
+
+pub async fn import_todo<T: TodoRepository>(
+    ValidatedJson(payload): ValidatedJson<ExportBundle>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let schema_version = payload.schema_version;
+    let payload = payload.upgrade().ok_or_else(|| {
+        let message = format!(
+            "Unsupported schema_version: [{}], expected {}",
+            schema_version,
+            ExportBundle::SCHEMA_VERSION
+        );
+        (StatusCode::BAD_REQUEST, message)
+    })?;
+
+    let imported = payload.todos.len();
+    let written = repository.import(payload.todos).await.map_err(|e| {
+        let message = match e.downcast_ref::<RepositoryError>() {
+            Some(RepositoryError::BadRequest(message)) => message.clone(),
+            _ => "Import failed.".to_string(),
+        };
+        (error_status(e), message)
+    })?;
+    for upserted in written {
+        webhooks.notify(match upserted {
+            Upserted::Created(todo) => TodoEvent::Created { todo },
+            Upserted::Updated(todo) => TodoEvent::Updated { todo },
+        });
+    }
+    Ok((StatusCode::OK, Json(serde_json::json!({ "imported": imported }))))
+}
+
+pub async fn schema_todo() -> impl IntoResponse {
+    let mut schema = serde_json::to_value(schemars::schema_for!(CreateTodo)).unwrap();
+    // the limit is runtime config, so the derive can't know it
+    schema["properties"]["text"]["maxLength"] = text_len::max_text_len().into();
+    (StatusCode::OK, Json(schema))
 }
 
 pub async fn update_todo<T: TodoRepository>(
     Path(id): Path<i32>,
-    ValidatedJson(payload): ValidatedJson<UpdateTodo>,
+    UpdatePayload(payload): UpdatePayload,
     Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let todo = repository
         .update(id, payload)
         .await
-        .or(Err(StatusCode::NOT_FOUND))?;
+        .map_err(error_status)?;
+    webhooks.notify(TodoEvent::Updated { todo: todo.clone() });
+    Ok((StatusCode::CREATED, Json(todo)))
+}
+
+pub async fn duplicate_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todo = repository.duplicate(id).await.map_err(error_status)?;
+    webhooks.notify(TodoEvent::Created { todo: todo.clone() });
     Ok((StatusCode::CREATED, Json(todo)))
 }
 
+pub async fn upsert_todo<T: TodoRepository>(
+    Path(uuid): Path<Uuid>,
+    ValidatedJson(payload): ValidatedJson<UpsertTodo>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    match repository.upsert(uuid, payload).await.map_err(error_status)? {
+        Upserted::Created(todo) => {
+            webhooks.notify(TodoEvent::Created { todo: todo.clone() });
+            Ok((StatusCode::CREATED, Json(todo)))
+        }
+        Upserted::Updated(todo) => {
+            webhooks.notify(TodoEvent::Updated { todo: todo.clone() });
+            Ok((StatusCode::OK, Json(todo)))
+        }
+    }
+}
+
+pub async fn bump_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+    Extension(config): Extension<Arc<Config>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let reordered = repository
+        .bump(id, config.default_sort_order)
+        .await
+        .map_err(error_status)?;
+    Ok((StatusCode::OK, Json(notify_reordered(&webhooks, reordered))))
+}
+
+pub async fn reorder_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<ReorderTodo>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let reordered = repository
+        .reorder(id, payload.position)
+        .await
+        .map_err(error_status)?;
+    Ok((StatusCode::OK, Json(notify_reordered(&webhooks, reordered))))
+}
+
+// Neighbours that shifted a slot are announced too, so a receiver mirroring positions stays in step.
+fn notify_reordered(webhooks: &Webhooks, reordered: Reordered) -> Todo {
+    webhooks.notify(TodoEvent::Updated { todo: reordered.todo.clone() });
+    for todo in reordered.shifted {
+        webhooks.notify(TodoEvent::Updated { todo });
+    }
+    reordered.todo
+}
+
+// Answers both todos, `a` first, with their new positions.
+pub async fn swap_todo<T: TodoRepository>(
+    ValidatedJson(payload): ValidatedJson<SwapTodos>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let (a, b) = repository
+        .swap(payload.a, payload.b)
+        .await
+        .map_err(error_status)?;
+    for todo in [&a, &b] {
+        webhooks.notify(TodoEvent::Updated { todo: todo.clone() });
+    }
+    Ok((StatusCode::OK, Json([a, b])))
+}
+
+pub async fn complete_all_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    set_all_completed(repository, webhooks, true).await
+}
+
+pub async fn uncomplete_all_todo<T: TodoRepository>(
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    set_all_completed(repository, webhooks, false).await
+}
+
+async fn set_all_completed<T: TodoRepository>(
+    repository: Arc<T>,
+    webhooks: Webhooks,
+    completed: bool,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todos = repository
+        .set_all_completed(completed)
+        .await
+        .map_err(error_status)?;
+    let affected = todos.len();
+    for todo in todos {
+        webhooks.notify(TodoEvent::Updated { todo });
+    }
+    Ok((StatusCode::OK, Json(serde_json::json!({ "affected": affected }))))
+}
+
+pub async fn replace_text_todo<T: TodoRepository>(
+    ValidatedJson(payload): ValidatedJson<ReplaceText>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let replaced = repository
+        .replace_text(&payload.from, &payload.to)
+        .await
+        .map_err(error_status)?;
+    let affected = replaced.len();
+    for todo in replaced {
+        webhooks.notify(TodoEvent::Updated { todo });
+    }
+    Ok((StatusCode::OK, Json(serde_json::json!({ "affected": affected }))))
+}
+
+pub async fn save_draft_todo(
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<Draft>,
+    Extension(drafts): Extension<DraftStore>,
+) -> StatusCode {
+    drafts.save(id, payload);
+    StatusCode::NO_CONTENT
+}
+
+pub async fn find_draft_todo(
+    Path(id): Path<i32>,
+    Extension(drafts): Extension<DraftStore>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let draft = drafts.get(id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok((StatusCode::OK, Json(draft)))
+}
+
+pub async fn commit_draft_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(drafts): Extension<DraftStore>,
+    Extension(webhooks): Extension<Webhooks>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // Drafts may be empty mid-edit, so the commit is held to the same rules as a PATCH; a draft that
+    // fails them stays saved for the client to fix.
+    let draft = drafts.get(id).ok_or(StatusCode::NOT_FOUND)?;
+    let payload = UpdateTodo::text(draft.text);
+    payload.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    drafts.take(id);
+    let todo = repository
+        .update(id, payload)
+        .await
+        .map_err(error_status)?;
+    webhooks.notify(TodoEvent::Updated { todo: todo.clone() });
+    Ok((StatusCode::OK, Json(todo)))
+}
+
+pub async fn share_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    Extension(repository): Extension<Arc<T>>,
+    Extension(signer): Extension<ShareSigner>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let todo = repository.find(id).await.map_err(error_status)?;
+    let token = signer.sign(&todo.into()).map_err(|e| {
+        tracing::error!("cannot sign share token: {:#}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "token": token }))))
+}
+
+// Answers from the token alone; the todo may since have changed or been deleted.
+pub async fn shared_todo(
+    Path(token): Path<String>,
+    Extension(signer): Extension<ShareSigner>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let shared = signer.verify(&token).ok_or(StatusCode::BAD_REQUEST)?;
+    Ok((StatusCode::OK, Json(shared)))
+}
+
+pub async fn history_todo<T: TodoRepository>(
+    Path(id): Path<i32>,
+    Extension(repository): Extension<Arc<T>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let entries = repository.history(id).await.map_err(error_status)?;
+    Ok((StatusCode::OK, Json(entries)))
+}
+
 pub async fn delete_todo<T: TodoRepository>(
     Path(id): Path<i32>,
     Extension(repository): Extension<Arc<T>>,
+    Extension(webhooks): Extension<Webhooks>,
 ) -> StatusCode {
     repository
         .delete(id)
         .await
-        .map(|_| StatusCode::NO_CONTENT)
-        .unwrap_or(StatusCode::NOT_FOUND)
+        .map(|_| {
+            webhooks.notify(TodoEvent::Deleted { id });
+            StatusCode::NO_CONTENT
+        })
+        .unwrap_or_else(error_status)
+}
+
+// Maps a repository failure onto the status the client should see.
+fn error_status(e: anyhow::Error) -> StatusCode {
+    match e.downcast_ref::<RepositoryError>() {
+        Some(RepositoryError::NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(RepositoryError::Duplicate(_)) | Some(RepositoryError::Conflict(_)) => StatusCode::CONFLICT,
+        Some(RepositoryError::BadRequest(_)) => StatusCode::BAD_REQUEST,
+        Some(RepositoryError::Unavailable(_)) => StatusCode::SERVICE_UNAVAILABLE,
+        Some(RepositoryError::Unexpected(_)) | None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// Installed via `CatchPanicLayer` so a panicking handler still answers instead of dropping the connection.
+pub fn panic_response(err: Box<dyn Any + Send + 'static>) -> Response {
+    let message = err
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| err.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown panic payload");
+    tracing::error!("handler panicked: {}", message);
+
+    let body = Json(serde_json::json!({ "error": "internal server error" }));
+    (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+}
+
+// Picked from the `Accept` header; anything other than MessagePack gets JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    MsgPack,
+}
+
+impl Format {
+    pub const MSGPACK: &'static str = "application/msgpack";
+
+    pub fn encode<S: Serialize>(self, body: S) -> Response {
+        match self {
+            Format::Json => Json(body).into_response(),
+            Format::MsgPack => match rmp_serde::to_vec_named(&body) {
+                Ok(bytes) => (Headers([(header::CONTENT_TYPE, Self::MSGPACK)]), bytes).into_response(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            },
+        }
+    }
+
+    // The encoding is part of the tag so a cached JSON body is never revalidated as MessagePack.
+    pub fn etag(self, revision: i64) -> String {
+        let suffix = match self {
+            Format::Json => "json",
+            Format::MsgPack => "msgpack",
+        };
+        format!("\"r{}-{}\"", revision, suffix)
+    }
+}
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for Format {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let wants_msgpack = req
+            .headers()
+            .and_then(|headers| headers.get(header::ACCEPT))
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains(Self::MSGPACK));
+        Ok(if wants_msgpack { Format::MsgPack } else { Format::Json })
+    }
+}
+
+pub struct IfNoneMatch(pub Option<String>);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for IfNoneMatch {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let tag = req
+            .headers()
+            .and_then(|headers| headers.get(header::IF_NONE_MATCH))
+            .and_then(|tag| tag.to_str().ok())
+            .map(|tag| tag.trim().to_string());
+        Ok(IfNoneMatch(tag))
+    }
+}
+
+// `?consistent=true` reads the page, its total and its revision from one snapshot, at the cost of a
+// transaction per request.
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyQuery {
+    #[serde(default)]
+    consistent: bool,
+}
+
+impl ConsistencyQuery {
+    pub const TOTAL_COUNT: &'static str = "x-total-count";
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldsQuery {
+    fields: Option<String>,
+}
+
+impl FieldsQuery {
+    // `?fields=id,text` keeps only the listed keys of each serialized todo.
+    fn select<S: Serialize>(&self, todo: S) -> Result<Value, StatusCode> {
+        let value = serde_json::to_value(todo).or(Err(StatusCode::INTERNAL_SERVER_ERROR))?;
+        let fields = match &self.fields {
+            Some(fields) => fields
+                .split(',')
+                .map(str::trim)
+                .filter(|field| !field.is_empty())
+                .collect::<Vec<_>>(),
+            None => return Ok(value),
+        };
+        if fields.iter().any(|field| !Todo::FIELDS.contains(field)) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        let retain = |value: Value| match value {
+            Value::Object(mut map) => {
+                map.retain(|key, _| fields.contains(&key.as_str()));
+                Value::Object(map)
+            }
+            other => other,
+        };
+        Ok(match value {
+            Value::Array(todos) => Value::Array(todos.into_iter().map(retain).collect()),
+            todo => retain(todo),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct Pagination {
+    #[validate(range(min = 0, message = "Can not be negative."))]
+    limit: Option<i64>,
+    #[validate(range(min = 0, message = "Can not be negative."))]
+    offset: Option<i64>,
+    order: Option<SortOrder>,
+}
+
+impl Pagination {
+    pub const DEFAULT_LIMIT: i64 = 20;
+
+    // Requests above the configured page size are clamped rather than rejected.
+    pub fn limit(&self, config: &Config) -> i64 {
+        self.limit
+            .unwrap_or(Self::DEFAULT_LIMIT)
+            .min(config.max_page_size)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.offset.unwrap_or(0)
+    }
+
+    pub fn order(&self, config: &Config) -> SortOrder {
+        self.order.unwrap_or(config.default_sort_order)
+    }
+}
+
+// `Json` whose syntax errors answer with a JSON body carrying serde's line/column.
+#[derive(Debug)]
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    B: http_body::Body + Send,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req).await {
+            Ok(Json(value)) => Ok(JsonBody(value)),
+            Err(JsonRejection::InvalidJsonBody(rejection)) => {
+                let detail = rejection
+                    .source()
+                    .map_or_else(|| rejection.to_string(), ToString::to_string);
+                let body = serde_json::json!({ "error": "invalid JSON", "detail": detail });
+                Err((StatusCode::BAD_REQUEST, Json(body)).into_response())
+            }
+            Err(rejection) => {
+                let message = format!("Json parse error: [{}]", rejection);
+                Err((StatusCode::BAD_REQUEST, message).into_response())
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -72,18 +797,142 @@ where
     B::Data: Send,
     B::Error: Into<BoxError>,
 {
-    type Rejection = (StatusCode, String);
+    type Rejection = Response;
 
     async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
-        let Json(value) = Json::<T>::from_request(req).await.map_err(|rejection| {
-            let message = format!("Json parse error: [{}]", rejection);
-            (StatusCode::BAD_REQUEST, message)
+        let JsonBody(value) = JsonBody::<Value>::from_request(req).await?;
+        // well-formed JSON that doesn't fit `T` (e.g. unknown keys) is a 422, not a 400
+        let value = serde_json::from_value::<T>(value).map_err(|rejection| {
+            let message = format!("Json data error: [{}]", rejection);
+            (StatusCode::UNPROCESSABLE_ENTITY, message).into_response()
         })?;
         value.validate().map_err(|rejection| {
             let message = format!("Validation error: [{}]", rejection).replace('\n', ", ");
-            (StatusCode::BAD_REQUEST, message)
+            (StatusCode::BAD_REQUEST, message).into_response()
         })?;
 
         Ok(ValidatedJson(value))
     }
 }
+
+// `PATCH /todos/:id` takes either a plain `UpdateTodo` or an `application/json-patch+json` array.
+#[derive(Debug)]
+pub struct UpdatePayload(UpdateTodo);
+
+#[async_trait]
+impl<B> FromRequest<B> for UpdatePayload
+where
+    B: http_body::Body + Send,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let is_json_patch = req
+            .headers()
+            .and_then(|headers| headers.get(header::CONTENT_TYPE))
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("application/json-patch+json"));
+        if !is_json_patch {
+            let ValidatedJson(payload) = ValidatedJson::<UpdateTodo>::from_request(req).await?;
+            return Ok(UpdatePayload(payload));
+        }
+
+        let Json(operations) = Json::<Vec<PatchOperation>>::from_request(req).await.map_err(|rejection| {
+            let message = format!("Json patch error: [{}]", rejection);
+            (StatusCode::BAD_REQUEST, message).into_response()
+        })?;
+        let payload = UpdateTodo::from_patch(operations).map_err(|rejection| {
+            let message = format!("Json patch error: [{}]", rejection);
+            (StatusCode::BAD_REQUEST, message).into_response()
+        })?;
+        payload.validate().map_err(|rejection| {
+            let message = format!("Validation error: [{}]", rejection).replace('\n', ", ");
+            (StatusCode::BAD_REQUEST, message).into_response()
+        })?;
+
+        Ok(UpdatePayload(payload))
+    }
+}
+
+#[derive(Debug)]
+pub struct ValidatedQuery<T>(T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    B: Send,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let QueryParams(value) = QueryParams::<T>::from_request(req).await?;
+        value.validate().map_err(|errors| {
+            let field_errors = errors.field_errors();
+            let mut fields = Vec::from_iter(field_errors.keys());
+            fields.sort();
+            let message = fields.first().map_or_else(
+                || format!("invalid query string: {}", errors),
+                |field| {
+                    let detail = field_errors[*field]
+                        .first()
+                        .and_then(|error| error.message.clone())
+                        .unwrap_or_else(|| "is invalid".into());
+                    format!("invalid query parameter '{}': {}", field, detail)
+                },
+            );
+            query_rejection(message)
+        })?;
+
+        Ok(ValidatedQuery(value))
+    }
+}
+
+// Replaces axum's `Query` so a bad value names its parameter. Unknown parameters are ignored,
+// because several extractors read the same query string and each only knows its own keys.
+#[derive(Debug)]
+pub struct QueryParams<T>(T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for QueryParams<T>
+where
+    T: DeserializeOwned,
+    B: Send,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let query = req.uri().query().unwrap_or_default();
+        let deserializer = serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        match serde_path_to_error::deserialize(deserializer) {
+            Ok(value) => Ok(QueryParams(value)),
+            Err(error) => {
+                let detail = describe_query_error(&error.inner().to_string());
+                let message = match error.path().to_string().as_str() {
+                    "." => format!("invalid query string: {}", detail),
+                    path => format!("invalid query parameter '{}': {}", path, detail),
+                };
+                Err(query_rejection(message))
+            }
+        }
+    }
+}
+
+// Values arrive as strings, so a wrong type only shows up as the message of the failed `parse`.
+fn describe_query_error(message: &str) -> String {
+    match message {
+        "invalid digit found in string" | "cannot parse integer from empty string" => "expected integer".to_string(),
+        "number too large to fit in target type" | "number too small to fit in target type" => {
+            "integer out of range".to_string()
+        }
+        "provided string was not `true` or `false`" => "expected true or false".to_string(),
+        message => message.to_string(),
+    }
+}
+
+fn query_rejection(message: String) -> Response {
+    let body = serde_json::json!({ "error": message });
+    (StatusCode::BAD_REQUEST, Json(body)).into_response()
+}
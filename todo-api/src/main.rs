@@ -1,71 +1,293 @@
+mod archive;
+mod drafts;
+mod maintenance;
 mod repositories;
+mod share;
 mod handlers;
+mod single_flight;
+#[cfg(feature = "dev-admin")]
+mod stats;
 mod util;
+mod webhook;
 
 use axum::{
+    body::Body,
     extract::Extension,
-    routing::{get, post},
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    response::Response,
+    routing::{get, post, put, Route},
     Router
 };
+use axum_server::tls_rustls::RustlsConfig;
+use futures::FutureExt;
 use std::net::SocketAddr;
 use std::{
-    env,
-    sync::Arc
+    sync::Arc,
+    time::Duration
+};
+use tower::{
+    layer::layer_fn,
+    limit::GlobalConcurrencyLimitLayer,
+    service_fn,
+    util::{AndThenLayer, MapRequestLayer, MapResponseLayer},
+    ServiceBuilder, ServiceExt,
+};
+use tower_http::{
+    catch_panic::CatchPanicLayer,
+    cors::{self, CorsLayer},
 };
 
-use crate::repositories::{TodoRepository, TodoRepositoryForDb};
-use crate::handlers::{all_todo, create_todo, update_todo, find_todo, delete_todo};
-use crate::util::database;
+use crate::drafts::DraftStore;
+use crate::maintenance::{MaintenanceMode, ReadOnlyMode};
+use crate::share::ShareSigner;
+use crate::single_flight::SingleFlight;
+use crate::webhook::{Dispatcher, Webhooks};
+use crate::repositories::{memory::TodoRepositoryForMemory, TodoRepository, TodoRepositoryForDb};
+use crate::handlers::{
+    all_todo, board_todo, bump_todo, commit_draft_todo, complete_all_todo, completed_todo,
+    count_todo, create_todo, delete_todo, duplicate_todo, exists_todo, export_todo,
+    export_todo_ndjson, find_by_text_todo, find_draft_todo, find_many_by_uuid_todo, find_todo,
+    history_todo, import_todo, latest_todo, oldest_todo, panic_response, progress_todo,
+    reorder_todo, replace_text_todo, sample_todo, save_draft_todo, schema_todo, share_todo,
+    shared_todo, swap_todo, sync_todo, top_todo, uncomplete_all_todo, update_todo, upsert_todo,
+    window_todo, word_stats_todo,
+};
+use crate::util::{
+    config::Config, database::{self, Backend}, flexible_bool, id_format, json_style::JsonStyle, logging, shutdown,
+    text_len, tls, trailing_slash,
+};
 
 #[tokio::main]
 async fn main() {
-    // logging
-    let log_level = env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
-    env::set_var("RUST_LOG", log_level);
-    tracing_subscriber::fmt::init();
+    let config = Config::from_env();
+    logging::init_tracing(config.log_directives.as_deref());
+    tracing::info!("effective config: {}", config.redacted_display());
 
-    let pool = database::init().await;
+    id_format::set_id_as_string(config.id_as_string);
+    text_len::set_max_text_len(config.max_todo_text_len);
+    let backend = database::init(&config).await;
     tracing::debug!("start connect database...");
 
-    let repository = TodoRepositoryForDb::new(pool.clone());
-    let app = create_app(repository);
+    let tls = tls::from_config(&config)
+        .await
+        .unwrap_or_else(|e| panic!("TLS CONFIG IS INVALID: {:#}", e));
+    let webhooks = Webhooks::from_url(config.webhook_url.as_deref())
+        .unwrap_or_else(|e| panic!("WEBHOOK_URL IS INVALID: {:#}", e));
+
+    match backend {
+        Backend::Database(pool) => {
+            serve(SingleFlight::new(TodoRepositoryForDb::new(pool)), config, tls, webhooks).await
+        }
+        Backend::Memory => serve(TodoRepositoryForMemory::new(), config, tls, webhooks).await,
+    }
+}
+
+async fn serve<T: TodoRepository>(
+    repository: T,
+    config: Config,
+    tls: Option<RustlsConfig>,
+    (webhooks, dispatcher): (Webhooks, Dispatcher),
+) {
+    let grace = Duration::from_secs(config.shutdown_grace_secs);
+    let signal = shutdown::signal().shared();
+    let read_only = ReadOnlyMode::new(config.read_only_mode);
+    let archiver = tokio::spawn(archive::run(
+        repository.clone(),
+        webhooks.clone(),
+        read_only.clone(),
+        Duration::from_secs(config.auto_archive_interval_secs),
+        chrono::Duration::days(config.auto_archive_days),
+        signal.clone(),
+    ));
+    let app = create_app(repository, config, webhooks, read_only);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    tracing::debug!("listening on {}", addr);
+    match tls {
+        Some(tls) => {
+            tracing::info!("listening on {} (protocols: h2, http/1.1 over TLS)", addr);
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                let signal = signal.clone();
+                async move {
+                    signal.await;
+                    handle.graceful_shutdown(Some(grace));
+                }
+            });
+            axum_server::bind_rustls(addr, tls)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+                .unwrap();
+        }
+        None => {
+            // hyper sniffs the HTTP/2 preface on the same plaintext port, so h2c needs no extra listener
+            tracing::info!("listening on {} (protocols: http/1.1, h2c)", addr);
+            let server = axum::Server::bind(&addr)
+                .serve(app.into_make_service())
+                .with_graceful_shutdown(signal.clone());
+            match shutdown::drain_within(server, signal, grace).await {
+                Some(result) => result.unwrap(),
+                None => tracing::warn!("requests still in flight after {:?}, forcing shutdown", grace),
+            }
+        }
+    }
+    archiver.await.ok();
+    // the app and the archiver held the last senders, so what is queued now is all that is left
+    dispatcher.drain(grace).await;
+}
 
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+// `read_only` comes in from the caller so background jobs can honour the same toggle.
+fn create_app<T: TodoRepository>(repository: T, config: Config, webhooks: Webhooks, read_only: ReadOnlyMode) -> Router {
+    let app = Router::new()
+        .nest("/v1", v1_routes::<T>())
+        .merge(v1_routes::<T>().layer(MapRequestLayer::new(warn_unversioned)))
+        .layer(MapResponseLayer::new(retry_soon));
+    #[cfg(feature = "dev-admin")]
+    let app = stats::mount(app);
+    let app = match JsonStyle::from_config(&config) {
+        Some(style) => app.layer(AndThenLayer::new(move |res| style.rewrite(res))),
+        None => app,
+    };
+    let maintenance = MaintenanceMode::new(config.maintenance_mode);
+    // `/ready` sits inside the guard, so maintenance mode also takes the instance out of rotation.
+    let app = app.route("/ready", get(maintenance::ready::<T>));
+    let app = maintenance::read_only_guard(app, read_only.clone());
+    let app = maintenance::guard(app, maintenance.clone()).route("/health", get(maintenance::health));
+    #[cfg(feature = "dev-admin")]
+    let app = app
+        .route("/admin/maintenance", put(maintenance::toggle))
+        .route("/admin/read-only", put(maintenance::toggle_read_only));
+
+    let trim_trailing_slash = config.trim_trailing_slash;
+    let log_sample_rate = config.log_sample_rate;
+    let app = scope_json_settings(app, &config)
+        .layer(Extension(maintenance))
+        .layer(Extension(read_only))
+        .layer(Extension(Arc::new(repository)))
+        .layer(Extension(DraftStore::new(Duration::from_secs(config.draft_ttl_secs))))
+        .layer(Extension(webhooks))
+        .layer(Extension(ShareSigner::from_secret(config.jwt_secret.as_deref())))
+        .layer(cors_layer(&config))
+        // Excess requests wait here instead of piling onto the pool and timing out in `acquire`.
+        // The semaphore is shared because axum 0.4 layers every route separately.
+        .layer(GlobalConcurrencyLimitLayer::new(config.max_concurrent_requests))
+        .layer(Extension(Arc::new(config)))
+        .layer(CatchPanicLayer::custom(panic_response));
+    let app = logging::log_requests(app, log_sample_rate);
+
+    // Layers on a `Router` only run after routing, so the rewrite wraps the whole app as a fallback.
+    if trim_trailing_slash {
+        Router::new().fallback(ServiceBuilder::new().map_request(trailing_slash::trim).service(app))
+    } else {
+        app
+    }
 }
 
-fn create_app<T: TodoRepository>(repository: T) -> Router {
+// Empty results: list endpoints answer 200 with `[]`, never 204; a missing todo addressed by id
+// is 404; endpoints that may have nothing to show by design (`/todos/latest`, `/todos/oldest`)
+// answer 204. `should_keep_empty_result_status_contract` pins this down.
+fn v1_routes<T: TodoRepository>() -> Router {
     Router::new()
         .route("/todos", post(create_todo::<T>).get(all_todo::<T>))
+        .route("/todos/board", get(board_todo::<T>))
+        .route("/todos/count", get(count_todo::<T>))
+        .route("/todos/progress", get(progress_todo::<T>))
+        .route("/todos/word-stats", get(word_stats_todo::<T>))
+        .route("/todos/top", get(top_todo::<T>))
+        .route("/todos/sample", get(sample_todo::<T>))
+        .route("/todos/window", get(window_todo::<T>))
+        .route("/todos/latest", get(latest_todo::<T>))
+        .route("/todos/oldest", get(oldest_todo::<T>))
+        .route("/todos/sync", get(sync_todo::<T>))
+        .route("/todos/completed", get(completed_todo::<T>))
+        .route("/todos/export", get(export_todo::<T>))
+        .route("/todos/export.ndjson", get(export_todo_ndjson::<T>))
+        .route("/todos/import", post(import_todo::<T>))
+        .route("/todos/schema", get(schema_todo))
+        .route("/todos/complete-all", post(complete_all_todo::<T>))
+        .route("/todos/uncomplete-all", post(uncomplete_all_todo::<T>))
+        .route("/todos/replace-text", post(replace_text_todo::<T>))
+        .route("/todos/swap", post(swap_todo::<T>))
         .route(
             "/todos/:id",
             get(find_todo::<T>)
+                .head(exists_todo::<T>)
                 .delete(delete_todo::<T>)
                 .patch(update_todo::<T>)
         )
-        .layer(Extension(Arc::new(repository)))
+        .route("/todos/:id/duplicate", post(duplicate_todo::<T>))
+        .route("/todos/by-uuid", post(find_many_by_uuid_todo::<T>))
+        .route("/todos/by-text", get(find_by_text_todo::<T>))
+        .route("/todos/by-uuid/:uuid", put(upsert_todo::<T>))
+        .route("/todos/:id/history", get(history_todo::<T>))
+        .route("/todos/:id/reorder", post(reorder_todo::<T>))
+        .route("/todos/:id/bump", post(bump_todo::<T>))
+        .route("/todos/:id/share", get(share_todo::<T>))
+        .route("/shared/:token", get(shared_todo))
+        .route("/todos/:id/draft", get(find_draft_todo).put(save_draft_todo))
+        .route("/todos/:id/draft/commit", post(commit_draft_todo::<T>))
+}
+
+// Serde attributes can't reach `Config`, so every request runs with the settings that shape its
+// JSON held in task-locals.
+fn scope_json_settings(app: Router, config: &Config) -> Router {
+    let (id_as_string, flexible_bools) = (config.id_as_string, config.flexible_bools);
+    app.layer(layer_fn(move |inner: Route| {
+        service_fn(move |req: Request<Body>| {
+            let inner = inner.clone();
+            id_format::scope(id_as_string, flexible_bool::scope(flexible_bools, inner.oneshot(req)))
+        })
+    }))
+}
+
+// The unversioned paths predate `/v1` and stay mounted as aliases until clients migrate.
+fn warn_unversioned<B>(req: Request<B>) -> Request<B> {
+    tracing::warn!("deprecated unversioned path {}, use /v1{}", req.uri().path(), req.uri().path());
+    req
+}
+
+// A 503 from a handler means the pool ran dry or the database is briefly away, so clients are told
+// to back off only a second. Responses that already carry a Retry-After keep it.
+fn retry_soon(mut res: Response) -> Response {
+    if res.status() == StatusCode::SERVICE_UNAVAILABLE && !res.headers().contains_key(header::RETRY_AFTER) {
+        res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    }
+    res
+}
+
+// Browsers cache the preflight for `cors_max_age_secs`, so edits here reach clients slowly.
+fn cors_layer(config: &Config) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(cors::Any)
+        .allow_methods(vec![Method::GET, Method::HEAD, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers(vec![header::CONTENT_TYPE])
+        .max_age(Duration::from_secs(config.cors_max_age_secs))
 }
 
 // unit test
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::repositories::{test_utils::TodoRepositoryForMemory, CreateTodo, Todo};
+    use crate::repositories::{
+        test_utils::{MockTodoRepository, TodoRepositoryForMemory},
+        AuditLog, CreateTodo, ExportBundle, RepositoryError, SortOrder, Todo, TodoBoard, TodoProgress,
+        TodoSync, UpdateTodo, WordStats,
+    };
     use axum::{body::Body,
-        http::{
-            header,
-            Method,
-            Request,
-            StatusCode
-        }, response::Response
+        http::StatusCode,
+        response::Response
     };
+    use proptest::prelude::*;
     use tower::ServiceExt;
 
+    // Every test app gets its own dispatcher; only `serve` needs the handle to drain it.
+    fn create_app<T: TodoRepository>(repository: T, config: Config) -> Router {
+        let (webhooks, _) = Webhooks::from_url(config.webhook_url.as_deref()).unwrap();
+        let read_only = ReadOnlyMode::new(config.read_only_mode);
+        super::create_app(repository, config, webhooks, read_only)
+    }
+
     fn build_todo_req_with_json(path: &str, method: Method, json_body: String) -> Request<Body> {
         Request::builder()
             .uri(path)
@@ -100,9 +322,578 @@ mod test {
             Method::POST,
             r#" { "text": "should_return_created_todo" }"#.to_string(),
         );
-        let res = create_app(repository).oneshot(req).await.unwrap();
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let todo = res_to_todo(res).await;
+        assert_eq!(expected, todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_notify_webhook_on_create() {
+        let (webhook_url, mut received) = webhook::test_utils::receiver(0);
+        let config = Config {
+            webhook_url: Some(webhook_url),
+            ..Config::default()
+        };
+        let req = build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "hooked" }"#.to_string());
+        let res = create_app(TodoRepositoryForMemory::new(), config).oneshot(req).await.unwrap();
+        let todo = res_to_todo(res).await;
+
+        let body = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("webhook did not fire")
+            .unwrap();
+        assert_eq!(serde_json::json!({ "event": "created", "todo": todo }), body);
+    }
+
+    #[tokio::test]
+    async fn should_notify_webhook_from_bulk_and_positional_mutators() {
+        let (webhook_url, mut received) = webhook::test_utils::receiver(0);
+        let config = Config {
+            webhook_url: Some(webhook_url),
+            ..Config::default()
+        };
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+        let app = create_app(repository, config);
+        async fn next_events(
+            received: &mut tokio::sync::mpsc::UnboundedReceiver<serde_json::Value>,
+            n: usize,
+        ) -> Vec<(String, String)> {
+            let mut events = vec![];
+            for _ in 0..n {
+                let body = tokio::time::timeout(Duration::from_secs(5), received.recv())
+                    .await
+                    .expect("webhook did not fire")
+                    .unwrap();
+                events.push((body["event"].as_str().unwrap().to_string(), body["todo"]["text"].as_str().unwrap().to_string()));
+            }
+            events
+        }
+        let updated = |text: &str| ("updated".to_string(), text.to_string());
+
+        // moving the last todo to the top shifts both others down a slot
+        let req = build_todo_req_with_empty(Method::POST, "/todos/3/bump");
+        assert_eq!(StatusCode::OK, app.clone().oneshot(req).await.unwrap().status());
+        assert_eq!(vec![updated("todo 2"), updated("todo 0"), updated("todo 1")], next_events(&mut received, 3).await);
+
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/duplicate");
+        assert_eq!(StatusCode::CREATED, app.clone().oneshot(req).await.unwrap().status());
+        assert_eq!(vec![("created".to_string(), "todo 0 (copy)".to_string())], next_events(&mut received, 1).await);
+
+        let req = build_todo_req_with_empty(Method::POST, "/todos/complete-all");
+        assert_eq!(StatusCode::OK, app.oneshot(req).await.unwrap().status());
+        let events = next_events(&mut received, 4).await;
+        assert!(events.iter().all(|(event, _)| event == "updated"), "{:?}", events);
+    }
+
+    #[tokio::test]
+    async fn should_created_todo_with_uuid() {
+        let uuid = uuid::Uuid::new_v4();
+        let repository = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_json(
+            "/todos",
+            Method::POST,
+            format!(r#"{{ "text": "should_created_todo_with_uuid", "uuid": "{}" }}"#, uuid),
+        );
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        let todo = res_to_todo(res).await;
+        assert_eq!(uuid, todo.uuid);
+    }
+
+    #[tokio::test]
+    async fn should_return_existing_todo_on_duplicate_uuid() {
+        let uuid = uuid::Uuid::new_v4();
+        let repository = TodoRepositoryForMemory::new();
+        let created = repository
+            .create(CreateTodo::with_uuid("first attempt".to_string(), uuid))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_json(
+            "/todos",
+            Method::POST,
+            format!(r#"{{ "text": "retried attempt", "uuid": "{}" }}"#, uuid),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CONFLICT, res.status());
         let todo = res_to_todo(res).await;
-        assert_eq!(expected, todo);
+        assert_eq!(created, todo);
+        assert_eq!(1, repository.all().await.unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn should_reject_create_with_unknown_field() {
+        let repository = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_json(
+            "/todos",
+            Method::POST,
+            r#"{ "text": "x", "owner_id": 99 }"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, res.status());
+        assert!(repository.all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_reject_control_characters_in_text() {
+        let repository = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_json(
+            "/todos",
+            Method::POST,
+            r#"{ "text": "null\u0000byte" }"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        assert!(repository.all().await.unwrap().is_empty());
+
+        let req = build_todo_req_with_json(
+            "/todos",
+            Method::POST,
+            r#"{ "text": "tab\tis fine" }"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+
+        let req = build_todo_req_with_json(
+            "/todos/1",
+            Method::PATCH,
+            r#"{ "text": "escape\u001b[31m" }"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        assert_eq!("tab\tis fine", repository.find(1).await.unwrap().text);
+    }
+
+    #[tokio::test]
+    async fn should_report_position_of_malformed_json() {
+        for (body, detail) in [
+            (r#"{ "text": "#, "EOF while parsing a value at line 1 column 10"),
+            (r#"{ "text": tru }"#, "expected ident at line 1 column 14"),
+            ("{ text }", "key must be a string at line 1 column 3"),
+        ] {
+            let repository = TodoRepositoryForMemory::new();
+            let req = build_todo_req_with_json("/todos", Method::POST, body.to_string());
+            let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::BAD_REQUEST, res.status(), "{}", body);
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(serde_json::json!({ "error": "invalid JSON", "detail": detail }), error);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_rename_response_keys_when_camel_case_enabled() {
+        for (json_camel_case, present, absent) in [(true, "updatedAt", "updated_at"), (false, "updated_at", "updatedAt")] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, 1).await;
+            let config = Config {
+                json_camel_case,
+                ..Config::default()
+            };
+            let req = build_todo_req_with_empty(Method::GET, "/todos/1");
+            let res = create_app(repository, config).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status());
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let todo: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert!(todo.get(present).is_some(), "{}", todo);
+            assert!(todo.get(absent).is_none(), "{}", todo);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_round_trip_export_with_json_style_enabled() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let config = Config {
+            json_camel_case: true,
+            json_pretty: true,
+            envelope_responses: true,
+            ..Config::default()
+        };
+        let res = create_app(repository.clone(), config.clone())
+            .oneshot(build_todo_req_with_empty(Method::GET, "/todos/export"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bundle = hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+        let imported = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_json("/todos/import", Method::POST, String::from_utf8(bundle.to_vec()).unwrap());
+        let res = create_app(imported.clone(), config).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(repository.all().await.unwrap(), imported.all().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_wrap_successful_json_in_envelope_when_enabled() {
+        for envelope_responses in [false, true] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, 1).await;
+            let config = Config {
+                envelope_responses,
+                ..Config::default()
+            };
+            let app = create_app(repository, config);
+
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/1")).await.unwrap();
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            if envelope_responses {
+                assert_eq!(1, body["data"]["id"], "{}", body);
+                assert!(body["meta"]["request_id"].as_str().is_some_and(|id| uuid::Uuid::parse_str(id).is_ok()), "{}", body);
+                assert!(body["meta"]["timestamp"].as_str().is_some_and(|at| at.parse::<chrono::DateTime<chrono::Utc>>().is_ok()), "{}", body);
+            } else {
+                assert_eq!(1, body["id"], "{}", body);
+                assert!(body.get("data").is_none(), "{}", body);
+            }
+
+            let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos?limit=abc")).await.unwrap();
+            assert_eq!(StatusCode::BAD_REQUEST, res.status());
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert!(body["error"].is_string(), "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_readiness_from_pending_migrations() {
+        let res = create_app(TodoRepositoryForMemory::new(), Config::default())
+            .oneshot(build_todo_req_with_empty(Method::GET, "/ready"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        let repository = MockTodoRepository::new().fail("pending_migrations", RepositoryError::Unavailable("down".to_string()));
+        let res = create_app(repository, Config::default())
+            .oneshot(build_todo_req_with_empty(Method::GET, "/ready"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_reject_writes_but_serve_reads_in_read_only_mode() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let config = Config {
+            read_only_mode: true,
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/1")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::HEAD, "/todos/1")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        let writes = [
+            build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "write" }"#.to_string()),
+            build_todo_req_with_json("/todos/1", Method::PATCH, r#"{ "completed": true }"#.to_string()),
+            build_todo_req_with_empty(Method::DELETE, "/todos/1"),
+        ];
+        for req in writes {
+            let res = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(serde_json::json!({ "error": "service is read-only" }), error);
+        }
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos/1")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_ask_for_retry_when_repository_is_unavailable() {
+        let repository = MockTodoRepository::new().fail("find", RepositoryError::Unavailable("pool timed out".to_string()));
+        let res = create_app(repository, Config::default())
+            .oneshot(build_todo_req_with_empty(Method::GET, "/todos/1"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+        assert_eq!("1", res.headers()[header::RETRY_AFTER]);
+    }
+
+    #[cfg(feature = "database-test")]
+    #[tokio::test]
+    async fn should_answer_503_with_retry_after_when_pool_is_exhausted() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE URL MUST BE SET.");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(1)
+            .connect_timeout(Duration::from_millis(200))
+            .connect(&database_url)
+            .await
+            .expect("failed create pool");
+        let app = create_app(TodoRepositoryForDb::new(pool.clone()), Config::default());
+
+        let _held = pool.acquire().await.expect("first acquire");
+        let responses = futures::future::join_all(
+            (0..4).map(|_| app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/1"))),
+        )
+        .await;
+        for res in responses {
+            let res = res.unwrap();
+            assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+            assert_eq!("1", res.headers()[header::RETRY_AFTER]);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_answer_503_except_health_in_maintenance_mode() {
+        let config = Config {
+            maintenance_mode: true,
+            ..Config::default()
+        };
+        let app = create_app(TodoRepositoryForMemory::new(), config);
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+        assert!(res.headers().contains_key(header::RETRY_AFTER));
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!({ "error": "service under maintenance" }), error);
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/health")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_upsert_todo_by_uuid() {
+        let repository = TodoRepositoryForMemory::new();
+        let app = create_app(repository.clone(), Config::default());
+        let path = format!("/todos/by-uuid/{}", uuid::Uuid::new_v4());
+
+        let req = build_todo_req_with_json(&path, Method::PUT, r#"{ "text": "offline" }"#.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        let created = res_to_todo(res).await;
+
+        let req = build_todo_req_with_json(&path, Method::PUT, r#"{ "text": "synced", "completed": true }"#.to_string());
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let updated = res_to_todo(res).await;
+        assert_eq!(created.id, updated.id);
+        assert_eq!("synced", updated.text);
+        assert!(updated.completed);
+        assert_eq!(vec![updated], repository.all().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_find_known_todos_by_uuid() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+        let todos = repository.all().await.unwrap();
+        let body = serde_json::json!({
+            "uuids": [todos[2].uuid, uuid::Uuid::new_v4(), todos[0].uuid],
+        });
+        let req = build_todo_req_with_json("/todos/by-uuid", Method::POST, body.to_string());
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(vec![todos[0].clone(), todos[2].clone()], res_to_todos(res).await);
+    }
+
+    #[tokio::test]
+    async fn should_create_only_if_absent() {
+        let repository = TodoRepositoryForMemory::new();
+        let app = create_app(repository.clone(), Config::default());
+        let create = || build_todo_req_with_json("/todos?if_absent=true", Method::POST, r#"{ "text": "once" }"#.to_string());
+
+        let res = app.clone().oneshot(create()).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        let created = res_to_todo(res).await;
+
+        let res = app.oneshot(create()).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(created, res_to_todo(res).await);
+        assert_eq!(vec![created], repository.all().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_conflict_on_duplicate_open_text() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(repository.clone(), Config::default());
+        let create = || build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "todo 0" }"#.to_string());
+
+        let res = app.clone().oneshot(create()).await.unwrap();
+        assert_eq!(StatusCode::CONFLICT, res.status());
+
+        repository.set_all_completed(true).await.expect("failed complete all");
+        let res = app.oneshot(create()).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_pretty_print_json_when_enabled() {
+        for json_pretty in [true, false] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, 1).await;
+            let config = Config {
+                json_pretty,
+                ..Config::default()
+            };
+            let req = build_todo_req_with_empty(Method::GET, "/todos/1");
+            let res = create_app(repository, config).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status());
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let body = String::from_utf8(bytes.to_vec()).unwrap();
+            assert_eq!(json_pretty, body.contains('\n'), "{}", body);
+            assert!(serde_json::from_str::<Todo>(&body).is_ok(), "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_share_todo_read_only() {
+        let repository = TodoRepositoryForMemory::new();
+        let todo = repository
+            .create(CreateTodo::new("should_share_todo".to_string()))
+            .await
+            .expect("failed create todo");
+        let config = Config {
+            jwt_secret: Some("secret".to_string()),
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/1/share")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let token = body["token"].as_str().unwrap();
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, &format!("/shared/{}", token))).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let shared: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!(todo.text), shared["text"]);
+        assert_eq!(None, shared.get("id"));
+
+        let tampered = format!("/shared/{}", token.replacen(|c: char| c.is_ascii_alphanumeric(), "_", 1));
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, &tampered)).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_route_trailing_slash_like_the_bare_path() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository, Config::default());
+
+        for path in ["/todos", "/todos/", "/v1/todos/"] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status(), "{}", path);
+            assert_eq!(2, res_to_todos(res).await.len(), "{}", path);
+        }
+        let req = build_todo_req_with_json("/todos/", Method::POST, r#"{ "text": "slash" }"#.to_string());
+        assert_eq!(StatusCode::CREATED, app.oneshot(req).await.unwrap().status());
+
+        let config = Config {
+            trim_trailing_slash: false,
+            ..Config::default()
+        };
+        let res = create_app(TodoRepositoryForMemory::new(), config)
+            .oneshot(build_todo_req_with_empty(Method::GET, "/todos/"))
+            .await
+            .unwrap();
+        // axum's own answer is a redirect to the bare path
+        assert_eq!(StatusCode::PERMANENT_REDIRECT, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_find_todos_by_exact_text() {
+        let repository = TodoRepositoryForMemory::new();
+        for text in ["buy milk", "Buy milk"] {
+            repository.create(CreateTodo::new(text.to_string())).await.expect("failed create todo");
+        }
+        let app = create_app(repository, Config::default());
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/by-text?text=buy+milk")).await.unwrap();
+        let todos = res_to_todos(res).await;
+        assert_eq!(vec!["buy milk"], todos.iter().map(|todo| todo.text.as_str()).collect::<Vec<_>>());
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/by-text?text=buy+milk+")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert!(res_to_todos(res).await.is_empty());
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos/by-text")).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_swap_todo_positions() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository, Config::default());
+
+        let req = build_todo_req_with_json("/todos/swap", Method::POST, r#"{ "a": 1, "b": 2 }"#.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let swapped = res_to_todos(res).await;
+        assert_eq!(vec![(1, 2), (2, 1)], swapped.iter().map(|todo| (todo.id, todo.position)).collect::<Vec<_>>());
+
+        let req = build_todo_req_with_json("/todos/swap", Method::POST, r#"{ "a": 1, "b": 3 }"#.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        assert_eq!(vec![2, 1], res_to_todos(res).await.iter().map(|todo| todo.id).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn should_return_window_around_todo() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 5).await;
+        let config = Config {
+            max_page_size: 2,
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+        let window = |path: &str| build_todo_req_with_empty(Method::GET, path);
+        let ids = |todos: Vec<Todo>| todos.into_iter().map(|todo| todo.id).collect::<Vec<_>>();
+
+        let res = app.clone().oneshot(window("/todos/window?id=3&radius=1")).await.unwrap();
+        assert_eq!(vec![2, 3, 4], ids(res_to_todos(res).await));
+        // the radius is held to half a page either side
+        let res = app.clone().oneshot(window("/todos/window?id=1&radius=10")).await.unwrap();
+        assert_eq!(vec![1, 2], ids(res_to_todos(res).await));
+
+        let res = app.clone().oneshot(window("/todos/window?id=3&radius=-1")).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let res = app.oneshot(window("/todos/window")).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_list_todos_completed_between() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository, Config::default());
+        let req = build_todo_req_with_json("/todos/1", Method::PATCH, r#"{ "completed": true }"#.to_string());
+        assert_eq!(StatusCode::CREATED, app.clone().oneshot(req).await.unwrap().status());
+
+        let res = app
+            .clone()
+            .oneshot(build_todo_req_with_empty(Method::GET, "/todos/completed?from=2000-01-01T00:00:00Z&to=2100-01-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(vec![1], res_to_todos(res).await.iter().map(|todo| todo.id).collect::<Vec<_>>());
+
+        let res = app
+            .clone()
+            .oneshot(build_todo_req_with_empty(Method::GET, "/todos/completed?from=2000-01-01T00:00:00Z&to=2001-01-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert!(res_to_todos(res).await.is_empty());
+
+        let res = app
+            .oneshot(build_todo_req_with_empty(Method::GET, "/todos/completed?from=2100-01-01T00:00:00Z&to=2000-01-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!({ "error": "invalid query parameter 'to': must be after 'from'" }), body);
     }
 
     #[tokio::test]
@@ -115,9 +906,60 @@ mod test {
             .await
             .expect("failed create todo");
         let req = build_todo_req_with_empty(Method::GET, "/todos/1");
-        let res = create_app(repository).oneshot(req).await.unwrap();
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
         let todo = res_to_todo(res).await;
-        assert_eq!(expected, todo);
+        assert_eq!(expected, todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_negotiate_todo_format() {
+        let expected = Todo::new(1, "should_negotiate_todo_format".to_string());
+        let repository = TodoRepositoryForMemory::new();
+        repository
+            .create(CreateTodo::new("should_negotiate_todo_format".to_string()))
+            .await
+            .expect("failed create todo");
+        let app = create_app(repository, Config::default());
+
+        let req = Request::builder()
+            .uri("/todos/1")
+            .header(header::ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!("application/json", res.headers()[header::CONTENT_TYPE]);
+        assert_eq!(expected, res_to_todo(res).await.without_generated());
+
+        let req = Request::builder()
+            .uri("/todos/1")
+            .header(header::ACCEPT, "application/msgpack")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!("application/msgpack", res.headers()[header::CONTENT_TYPE]);
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        // uuid travels as a string, as in JSON, so decode generically first
+        let value: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+        let todo: Todo = serde_json::from_value(value).unwrap();
+        assert_eq!(expected, todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_check_todo_existence_with_head() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(repository, Config::default());
+
+        let req = build_todo_req_with_empty(Method::HEAD, "/todos/1");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(bytes.is_empty());
+
+        let req = build_todo_req_with_empty(Method::HEAD, "/todos/2");
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
     }
 
     #[tokio::test]
@@ -130,46 +972,1337 @@ mod test {
             .await
             .expect("failed create todo");
         let req = build_todo_req_with_empty(Method::GET, "/todos");
-        let res = create_app(repository).oneshot(req).await.unwrap();
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
         let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
         let body: String = String::from_utf8(bytes.to_vec()).unwrap();
         let todo: Vec<Todo> = serde_json::from_str(&body)
             .unwrap_or_else(|_| panic!("cannot convert Todo instance. body: {}", body));
+        let todo: Vec<Todo> = todo.into_iter().map(Todo::without_generated).collect();
         assert_eq!(vec![expected], todo);
     }
 
-    #[tokio::test]
-    async fn should_update_todo() {
-        let expected = Todo::new(1, "should_update_todo".to_string());
+    fn build_todo_req_with_if_none_match(path: &str, etag: &str) -> Request<Body> {
+        Request::builder()
+            .uri(path)
+            .method(Method::GET)
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap()
+    }
 
+    #[tokio::test]
+    async fn should_sync_only_todos_changed_since() {
         let repository = TodoRepositoryForMemory::new();
-        repository.
-            create(CreateTodo::new("before_update_todo".to_string()))
+        create_todos(&repository, 3).await;
+        let since = repository.find(3).await.unwrap().updated_at;
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        repository
+            .update(1, UpdateTodo::text("todo 1 edited".to_string()))
             .await
-            .expect("failed create todo");
-        let req = build_todo_req_with_json(
-            "/todos/1",
-            Method::PATCH,
-            r#"{
-                "text": "should_update_todo",
-                "completed": false
-            }"#
-            .to_string(),
+            .expect("failed update todo");
+        repository.delete(2).await.expect("failed delete todo");
+        create_todos(&repository, 1).await;
+
+        let path = format!(
+            "/todos/sync?since={}",
+            since.to_rfc3339_opts(chrono::SecondsFormat::Nanos, true)
         );
-        let res = create_app(repository).oneshot(req).await.unwrap();
-        let todo = res_to_todo(res).await;
-        assert_eq!(expected, todo);
+        let req = build_todo_req_with_empty(Method::GET, &path);
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let sync: TodoSync = serde_json::from_slice(&bytes).unwrap();
+        let ids: Vec<i32> = sync.todos.iter().map(|todo| todo.id).collect();
+        assert_eq!(vec![1, 4], ids);
+        assert_eq!("todo 1 edited", sync.todos[0].text);
+        assert_eq!(vec![2], sync.deleted);
     }
 
     #[tokio::test]
-    async fn should_delete_todo() {
-        let repository = TodoRepositoryForMemory::new();
-        repository.
+    async fn should_reject_sync_without_valid_since() {
+        for path in ["/todos/sync", "/todos/sync?since=yesterday"] {
+            let repository = TodoRepositoryForMemory::new();
+            let req = build_todo_req_with_empty(Method::GET, path);
+            let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::BAD_REQUEST, res.status(), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_count_todos_by_completed() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+        repository.set_all_completed(true).await.expect("failed complete all");
+        create_todos(&repository, 1).await;
+        let app = create_app(repository, Config::default());
+
+        for (path, expected) in [("/todos/count", 4), ("/todos/count?completed=true", 3), ("/todos/count?completed=false", 1)] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status(), "{}", path);
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let count: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(serde_json::json!({ "count": expected }), count, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_completion_progress() {
+        for (open, completed, percent) in [(0, 0, 0.0), (0, 2, 100.0), (2, 1, 33.3)] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, completed).await;
+            repository.set_all_completed(true).await.expect("failed complete all");
+            create_todos(&repository, open).await;
+
+            let req = build_todo_req_with_empty(Method::GET, "/todos/progress");
+            let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status());
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let progress: TodoProgress = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(TodoProgress { completed: completed as i64, total: (open + completed) as i64, percent }, progress);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_get_top_open_todos() {
+        for (path, max_page_size, expected) in [
+            ("/todos/top", 100, vec![2, 3, 4, 5, 6]),
+            ("/todos/top?n=2", 100, vec![2, 3]),
+            ("/todos/top?n=50", 3, vec![2, 3, 4]),
+        ] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, 1).await;
+            repository.set_all_completed(true).await.expect("failed complete all");
+            create_todos(&repository, 7).await;
+            let config = Config {
+                max_page_size,
+                ..Config::default()
+            };
+            let req = build_todo_req_with_empty(Method::GET, path);
+            let res = create_app(repository, config).oneshot(req).await.unwrap();
+            let ids: Vec<i32> = res_to_todos(res).await.iter().map(|todo| todo.id).collect();
+            assert_eq!(expected, ids, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_sample_at_most_n_todos() {
+        for (path, created, expected) in [
+            ("/todos/sample?n=3", 5, 3),
+            ("/todos/sample?n=10", 4, 4),
+            ("/todos/sample", 12, 10),
+            ("/todos/sample?n=0", 2, 0),
+        ] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, created).await;
+            let req = build_todo_req_with_empty(Method::GET, path);
+            let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+            let mut ids: Vec<i32> = res_to_todos(res).await.iter().map(|todo| todo.id).collect();
+            assert_eq!(expected, ids.len(), "{}", path);
+            ids.sort_unstable();
+            ids.dedup();
+            assert_eq!(expected, ids.len(), "{}", path);
+            assert!(ids.iter().all(|id| (1..=created as i32).contains(id)), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_get_consistent_snapshot_of_todos() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 25).await;
+        repository.update(1, serde_json::from_str(r#"{ "completed": true }"#).unwrap()).await.unwrap();
+        repository.archive_completed(chrono::Utc::now() + chrono::Duration::days(1)).await.unwrap();
+        let config = Config {
+            max_page_size: 10,
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+
+        let req = build_todo_req_with_empty(Method::GET, "/todos?consistent=true&limit=50&offset=2&order=desc");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!("24", res.headers()["x-total-count"]);
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+        let ids: Vec<i32> = res_to_todos(res).await.into_iter().map(|todo| todo.id).collect();
+        assert_eq!((14..=23).rev().collect::<Vec<_>>(), ids);
+
+        let req = build_todo_req_with_if_none_match("/todos?consistent=true", &etag);
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_MODIFIED, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_revalidate_todo_list_with_etag() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository.clone(), Config::default());
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let etag = res.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        let res = app.clone().oneshot(build_todo_req_with_if_none_match("/todos", &etag)).await.unwrap();
+        assert_eq!(StatusCode::NOT_MODIFIED, res.status());
+        assert_eq!(etag, res.headers()[header::ETAG]);
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(bytes.is_empty());
+
+        repository
+            .create(CreateTodo::new("invalidates the etag".to_string()))
+            .await
+            .expect("failed create todo");
+        let res = app.oneshot(build_todo_req_with_if_none_match("/todos", &etag)).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_ne!(etag, res.headers()[header::ETAG]);
+        assert_eq!(3, res_to_todos(res).await.len());
+    }
+
+    async fn res_to_todos(res: Response) -> Vec<Todo> {
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: String = String::from_utf8(bytes.to_vec()).unwrap();
+        serde_json::from_str(&body)
+            .unwrap_or_else(|_| panic!("cannot convert Todo instance. body: {}", body))
+    }
+
+    async fn create_todos(repository: &TodoRepositoryForMemory, count: usize) {
+        for i in 0..count {
+            repository
+                .create(CreateTodo::new(format!("todo {}", i)))
+                .await
+                .expect("failed create todo");
+        }
+    }
+
+    #[tokio::test]
+    async fn should_get_default_page_of_todos() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 25).await;
+        let req = build_todo_req_with_empty(Method::GET, "/todos");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let todos = res_to_todos(res).await;
+        assert_eq!(20, todos.len());
+        assert_eq!(1, todos[0].id);
+    }
+
+    #[tokio::test]
+    async fn should_get_page_of_todos_with_offset() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 5).await;
+        let req = build_todo_req_with_empty(Method::GET, "/todos?limit=2&offset=3");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let ids: Vec<i32> = res_to_todos(res).await.iter().map(|todo| todo.id).collect();
+        assert_eq!(vec![4, 5], ids);
+    }
+
+    #[tokio::test]
+    async fn should_order_todos_by_configured_default_unless_requested() {
+        for (default_sort_order, path, expected) in [
+            (SortOrder::Asc, "/todos", vec![1, 2, 3]),
+            (SortOrder::Desc, "/todos", vec![3, 2, 1]),
+            (SortOrder::Desc, "/todos?order=asc", vec![1, 2, 3]),
+            (SortOrder::Asc, "/todos?order=desc&limit=2", vec![3, 2]),
+        ] {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, 3).await;
+            let config = Config {
+                default_sort_order,
+                ..Config::default()
+            };
+            let req = build_todo_req_with_empty(Method::GET, path);
+            let res = create_app(repository, config).oneshot(req).await.unwrap();
+            let ids: Vec<i32> = res_to_todos(res).await.iter().map(|todo| todo.id).collect();
+            assert_eq!(expected, ids, "{:?} {}", default_sort_order, path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_unknown_sort_order() {
+        let req = build_todo_req_with_empty(Method::GET, "/todos?order=random");
+        let res = create_app(TodoRepositoryForMemory::new(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_name_invalid_query_parameter() {
+        for (path, error) in [
+            ("/todos?limit=abc", "invalid query parameter 'limit': expected integer"),
+            ("/todos?offset=99999999999999999999", "invalid query parameter 'offset': integer out of range"),
+            ("/todos?limit=-1", "invalid query parameter 'limit': Can not be negative."),
+            ("/todos?consistent=maybe", "invalid query parameter 'consistent': expected true or false"),
+            ("/todos/count?completed=1", "invalid query parameter 'completed': expected true or false"),
+            ("/todos/sync", "invalid query string: missing field `since`"),
+        ] {
+            let req = build_todo_req_with_empty(Method::GET, path);
+            let res = create_app(TodoRepositoryForMemory::new(), Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::BAD_REQUEST, res.status(), "{}", path);
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(serde_json::json!({ "error": error }), body, "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_ignore_unknown_query_parameters() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let req = build_todo_req_with_empty(Method::GET, "/todos?limit=1&utm_source=mail");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(1, res_to_todos(res).await.len());
+    }
+
+    #[tokio::test]
+    async fn should_clamp_limit_to_max_page_size() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 5).await;
+        let config = Config {
+            max_page_size: 3,
+            ..Config::default()
+        };
+        let req = build_todo_req_with_empty(Method::GET, "/todos?limit=1000");
+        let res = create_app(repository, config).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(3, res_to_todos(res).await.len());
+    }
+
+    #[tokio::test]
+    async fn should_reject_negative_pagination() {
+        for path in ["/todos?offset=-1", "/todos?limit=-1"] {
+            let repository = TodoRepositoryForMemory::new();
+            let req = build_todo_req_with_empty(Method::GET, path);
+            let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::BAD_REQUEST, res.status(), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_find_todo_with_fields() {
+        let repository = TodoRepositoryForMemory::new();
+        repository
+            .create(CreateTodo::new("should_find_todo_with_fields".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1?fields=id,text");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let todo: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            serde_json::json!({ "id": 1, "text": "should_find_todo_with_fields" }),
+            todo
+        );
+    }
+
+    #[tokio::test]
+    async fn should_get_all_todos_with_fields() {
+        let repository = TodoRepositoryForMemory::new();
+        repository
+            .create(CreateTodo::new("should_get_all_todos_with_fields".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_empty(Method::GET, "/todos?fields=completed");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let todos: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!([{ "completed": false }]), todos);
+    }
+
+    #[tokio::test]
+    async fn should_reject_unknown_field() {
+        let repository = TodoRepositoryForMemory::new();
+        repository
+            .create(CreateTodo::new("should_reject_unknown_field".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1?fields=id,owner_id");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_export_todos_as_ndjson() {
+        let repository = TodoRepositoryForMemory::new();
+        for text in ["first", "second", "third"] {
+            repository
+                .create(CreateTodo::new(text.to_string()))
+                .await
+                .expect("failed create todo");
+        }
+        let req = build_todo_req_with_empty(Method::GET, "/todos/export.ndjson");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(
+            "application/x-ndjson",
+            res.headers()[header::CONTENT_TYPE].to_str().unwrap()
+        );
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: String = String::from_utf8(bytes.to_vec()).unwrap();
+        let todos: Vec<Todo> = body
+            .lines()
+            .map(|line| serde_json::from_str(line)
+                .unwrap_or_else(|_| panic!("cannot convert Todo instance. line: {}", line)))
+            .collect();
+        assert_eq!(3, todos.len());
+    }
+
+    #[tokio::test]
+    async fn should_describe_create_todo_schema() {
+        let repository = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_empty(Method::GET, "/todos/schema");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let schema: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let text = &schema["properties"]["text"];
+        assert_eq!("string", text["type"]);
+        assert_eq!(1, text["minLength"]);
+        assert_eq!(100, text["maxLength"]);
+        assert_eq!(serde_json::json!(["text"]), schema["required"]);
+    }
+
+    #[tokio::test]
+    async fn should_find_latest_todo() {
+        let repository = TodoRepositoryForMemory::new();
+        let app = create_app(repository.clone(), Config::default());
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/latest")).await.unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
+
+        create_todos(&repository, 3).await;
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/latest")).await.unwrap();
+        assert_eq!(3, res_to_todo(res).await.id);
+
+        repository
+            .update(1, serde_json::from_str(r#"{ "completed": true }"#).unwrap())
+            .await
+            .unwrap();
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos/latest")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let todo = res_to_todo(res).await;
+        assert_eq!(1, todo.id);
+        assert!(todo.completed);
+    }
+
+    #[tokio::test]
+    async fn should_find_oldest_open_todo() {
+        let repository = TodoRepositoryForMemory::new();
+        let app = create_app(repository.clone(), Config::default());
+        let oldest = || build_todo_req_with_empty(Method::GET, "/todos/oldest");
+        let res = app.clone().oneshot(oldest()).await.unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
+
+        create_todos(&repository, 4).await;
+        for id in [1, 3] {
+            repository
+                .update(id, serde_json::from_str(r#"{ "completed": true }"#).unwrap())
+                .await
+                .unwrap();
+        }
+        let res = app.clone().oneshot(oldest()).await.unwrap();
+        assert_eq!(2, res_to_todo(res).await.id);
+
+        repository.set_all_completed(true).await.unwrap();
+        let res = app.oneshot(oldest()).await.unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_keep_empty_result_status_contract() {
+        let app = create_app(TodoRepositoryForMemory::new(), Config::default());
+        for path in ["/todos", "/todos?limit=10&offset=5", "/todos/top"] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status(), "{}", path);
+            assert_eq!(Vec::<Todo>::new(), res_to_todos(res).await, "{}", path);
+        }
+        let req = build_todo_req_with_json("/todos/by-uuid", Method::POST, r#"{ "uuids": [] }"#.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(Vec::<Todo>::new(), res_to_todos(res).await);
+
+        for method in [Method::GET, Method::DELETE] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(method.clone(), "/todos/1")).await.unwrap();
+            assert_eq!(StatusCode::NOT_FOUND, res.status(), "{}", method);
+        }
+        let req = build_todo_req_with_json("/todos/1", Method::PATCH, r#"{ "completed": true }"#.to_string());
+        assert_eq!(StatusCode::NOT_FOUND, app.clone().oneshot(req).await.unwrap().status());
+
+        for path in ["/todos/latest", "/todos/oldest"] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!(StatusCode::NO_CONTENT, res.status(), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_count_words_across_todos() {
+        let repository = TodoRepositoryForMemory::new();
+        for text in ["Buy milk and eggs", "buy bread, then MILK!", "call the bank about milk's price", "Don't buy"] {
+            repository.create(CreateTodo::new(text.to_string())).await.expect("failed create todo");
+        }
+        let req = build_todo_req_with_empty(Method::GET, "/todos/word-stats");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let stats: WordStats = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(16, stats.total_words);
+        let top: Vec<(&str, u64)> = stats.top_words.iter().map(|w| (w.word.as_str(), w.count)).collect();
+        assert_eq!(
+            vec![
+                ("buy", 3),
+                ("milk", 2),
+                ("about", 1),
+                ("bank", 1),
+                ("bread", 1),
+                ("call", 1),
+                ("don't", 1),
+                ("eggs", 1),
+                ("milk's", 1),
+                ("price", 1),
+            ],
+            top
+        );
+    }
+
+    #[tokio::test]
+    async fn should_group_todos_on_board() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 4).await;
+        for id in [2, 3] {
+            let payload = serde_json::from_str(r#"{ "completed": true }"#).unwrap();
+            repository.update(id, payload).await.unwrap();
+        }
+        let req = build_todo_req_with_empty(Method::GET, "/todos/board");
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let board: TodoBoard = serde_json::from_slice(&bytes).unwrap();
+
+        let ids = |todos: &[Todo]| todos.iter().map(|todo| todo.id).collect::<Vec<_>>();
+        assert_eq!(vec![1, 4], ids(&board.open));
+        assert_eq!(vec![2, 3], ids(&board.completed));
+        assert!(board.open.iter().all(|todo| !todo.completed));
+        assert!(board.completed.iter().all(|todo| todo.completed));
+
+        let mut union = [board.open, board.completed].concat();
+        union.sort_by_key(|todo| todo.position);
+        assert_eq!(repository.all().await.unwrap(), union);
+    }
+
+    async fn export_bundle(app: Router) -> serde_json::Value {
+        let req = build_todo_req_with_empty(Method::GET, "/todos/export");
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_let_clients_cache_exports_only() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let config = Config {
+            export_cache_max_age_secs: 15,
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+        for path in ["/todos/export", "/todos/export.ndjson"] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!("private, max-age=15", res.headers()[header::CACHE_CONTROL], "{}", path);
+        }
+
+        let req = build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "fresh" }"#.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert!(res.headers().get(header::CACHE_CONTROL).is_none());
+        let req = build_todo_req_with_json("/todos/1", Method::PATCH, r#"{ "completed": true }"#.to_string());
+        let res = app.oneshot(req).await.unwrap();
+        assert!(res.status().is_success());
+        assert!(res.headers().get(header::CACHE_CONTROL).is_none());
+    }
+
+    #[tokio::test]
+    async fn should_round_trip_export_bundle() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+        repository.reorder(3, 1).await.unwrap();
+        let bundle = export_bundle(create_app(repository, Config::default())).await;
+        assert_eq!(ExportBundle::SCHEMA_VERSION, bundle["schema_version"]);
+
+        let imported = TodoRepositoryForMemory::new();
+        let app = create_app(imported, Config::default());
+        let req = build_todo_req_with_json("/todos/import", Method::POST, bundle.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        assert_eq!(bundle, export_bundle(app).await);
+    }
+
+    #[tokio::test]
+    async fn should_upgrade_v1_bundle_on_import() {
+        let repository = TodoRepositoryForMemory::new();
+        let bundle = serde_json::json!({
+            "schema_version": 1,
+            "todos": [{
+                "id": 7,
+                "text": "from v1",
+                "completed": true,
+                "uuid": "00000000-0000-0000-0000-000000000007",
+                "position": 1
+            }]
+        });
+        let req = build_todo_req_with_json("/todos/import", Method::POST, bundle.to_string());
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        let todo = repository.find(7).await.unwrap();
+        assert!(!todo.archived);
+        assert!(chrono::Utc::now() - todo.updated_at < chrono::Duration::minutes(1));
+    }
+
+    #[tokio::test]
+    async fn should_validate_and_renumber_imported_todos() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository.clone(), Config::default());
+        let import = |todos: serde_json::Value| {
+            let bundle = serde_json::json!({ "schema_version": ExportBundle::SCHEMA_VERSION, "todos": todos });
+            build_todo_req_with_json("/todos/import", Method::POST, bundle.to_string())
+        };
+        let todo = |id: i32, text: &str, position: i32| serde_json::to_value(Todo { position, ..Todo::new(id, text.to_string()) }).unwrap();
+
+        let res = app.clone().oneshot(import(serde_json::json!([todo(9, "ok", 1), todo(10, "", 2)]))).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(String::from_utf8(bytes.to_vec()).unwrap().contains("todo 10"));
+        assert_eq!(2, repository.all().await.unwrap().len());
+
+        let res = app.oneshot(import(serde_json::json!([todo(9, "first", 1), todo(10, "last", 40)]))).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let positions: Vec<(i32, i32)> = repository.all().await.unwrap().into_iter().map(|todo| (todo.id, todo.position)).collect();
+        assert_eq!(vec![(1, 1), (9, 2), (2, 3), (10, 4)], positions);
+        assert_eq!(AuditLog::CREATE, repository.history(10).await.unwrap()[0].action);
+        assert_eq!(AuditLog::UPDATE, repository.history(2).await.unwrap().last().unwrap().action);
+    }
+
+    #[tokio::test]
+    async fn should_reject_unsupported_bundle_version() {
+        let repository = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_json(
+            "/todos/import",
+            Method::POST,
+            r#"{ "schema_version": 99, "todos": [] }"#.to_string(),
+        );
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert!(String::from_utf8(bytes.to_vec()).unwrap().contains("schema_version"));
+    }
+
+    #[tokio::test]
+    async fn should_update_todo() {
+        let expected = Todo::new(1, "should_update_todo".to_string());
+
+        let repository = TodoRepositoryForMemory::new();
+        repository.
+            create(CreateTodo::new("before_update_todo".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_json(
+            "/todos/1",
+            Method::PATCH,
+            r#"{
+                "text": "should_update_todo",
+                "completed": false
+            }"#
+            .to_string(),
+        );
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let todo = res_to_todo(res).await;
+        assert_eq!(expected, todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_reject_update_with_unknown_field() {
+        let repository = TodoRepositoryForMemory::new();
+        repository.
+            create(CreateTodo::new("before_update_todo".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_json(
+            "/todos/1",
+            Method::PATCH,
+            r#"{ "text": "x", "owner_id": 99 }"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, res.status());
+        let todo = repository.find(1).await.unwrap();
+        assert_eq!(Todo::new(1, "before_update_todo".to_string()), todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_accept_only_booleans_without_flexible_bools() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(
+            repository,
+            Config {
+                flexible_bools: false,
+                ..Config::default()
+            },
+        );
+
+        let body = r#"{ "completed": "1" }"#.to_string();
+        let res = app.clone().oneshot(build_todo_req_with_json("/todos/1", Method::PATCH, body)).await.unwrap();
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, res.status());
+        let req = build_todo_req_with_json_patch(
+            "/todos/1",
+            r#"[{ "op": "replace", "path": "/completed", "value": 1 }]"#.to_string(),
+        );
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+
+        let body = r#"{ "completed": true }"#.to_string();
+        let res = app.oneshot(build_todo_req_with_json("/todos/1", Method::PATCH, body)).await.unwrap();
+        assert!(res_to_todo(res).await.completed);
+    }
+
+    #[tokio::test]
+    async fn should_serialize_ids_as_strings_when_configured() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(
+            repository,
+            Config {
+                id_as_string: true,
+                ..Config::default()
+            },
+        );
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos/1")).await.unwrap();
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let todo: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!("1"), todo["id"]);
+    }
+
+    #[tokio::test]
+    async fn should_accept_loosely_typed_completed() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(repository, Config::default());
+        for (completed, expected) in [
+            ("true", true), ("0", false), ("1", true), (r#""false""#, false), (r#""1""#, true), (r#""0""#, false),
+        ] {
+            let body = format!(r#"{{ "completed": {} }}"#, completed);
+            let res = app.clone().oneshot(build_todo_req_with_json("/todos/1", Method::PATCH, body)).await.unwrap();
+            assert!(res.status().is_success(), "{}", completed);
+            assert_eq!(expected, res_to_todo(res).await.completed, "{}", completed);
+        }
+
+        let body = r#"{ "completed": "yes" }"#.to_string();
+        let res = app.oneshot(build_todo_req_with_json("/todos/1", Method::PATCH, body)).await.unwrap();
+        assert_eq!(StatusCode::UNPROCESSABLE_ENTITY, res.status());
+    }
+
+    fn build_todo_req_with_json_patch(path: &str, json_body: String) -> Request<Body> {
+        Request::builder()
+            .uri(path)
+            .method(Method::PATCH)
+            .header(header::CONTENT_TYPE, "application/json-patch+json")
+            .body(Body::from(json_body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_update_todo_with_json_patch() {
+        let repository = TodoRepositoryForMemory::new();
+        repository.
+            create(CreateTodo::new("before_update_todo".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_json_patch(
+            "/todos/1",
+            r#"[
+                { "op": "replace", "path": "/completed", "value": true },
+                { "op": "replace", "path": "/text", "value": "patched" }
+            ]"#
+            .to_string(),
+        );
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let todo = res_to_todo(res).await;
+        let expected = Todo {
+            completed: true,
+            ..Todo::new(1, "patched".to_string())
+        };
+        assert_eq!(expected, todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_accept_flexible_bool_in_json_patch() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(repository, Config::default());
+
+        let req = build_todo_req_with_json_patch(
+            "/todos/1",
+            r#"[{ "op": "replace", "path": "/completed", "value": "1" }]"#.to_string(),
+        );
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert!(res_to_todo(res).await.completed);
+
+        let req = build_todo_req_with_json_patch(
+            "/todos/1",
+            r#"[{ "op": "replace", "path": "/completed", "value": "yes" }]"#.to_string(),
+        );
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_reject_unsupported_json_patch() {
+        let repository = TodoRepositoryForMemory::new();
+        repository.
+            create(CreateTodo::new("before_update_todo".to_string()))
+            .await
+            .expect("failed create todo");
+        let req = build_todo_req_with_json_patch(
+            "/todos/1",
+            r#"[{ "op": "add", "path": "/owner", "value": 99 }]"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let todo = repository.find(1).await.unwrap();
+        assert_eq!(Todo::new(1, "before_update_todo".to_string()), todo.without_generated());
+    }
+
+    #[tokio::test]
+    async fn should_duplicate_todo() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository.clone(), Config::default());
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/duplicate");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+        let todo = res_to_todo(res).await;
+        assert_eq!(Todo::new(3, "todo 0 (copy)".to_string()), todo.clone().without_generated());
+        assert_eq!(3, todo.position);
+        assert_eq!(todo, repository.find(3).await.unwrap());
+
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/duplicate");
+        let todo = res_to_todo(app.oneshot(req).await.unwrap()).await;
+        assert_eq!("todo 0 (copy 2)", todo.text);
+    }
+
+
+    #[tokio::test]
+    async fn should_not_duplicate_missing_todo() {
+        let repository = TodoRepositoryForMemory::new();
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/duplicate");
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+        assert!(repository.all().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_reorder_todo_to_start() {
+        let repository = TodoRepositoryForMemory::new();
+        for text in ["first", "second", "third"] {
+            repository
+                .create(CreateTodo::new(text.to_string()))
+                .await
+                .expect("failed create todo");
+        }
+        let req = build_todo_req_with_json(
+            "/todos/3/reorder",
+            Method::POST,
+            r#"{ "position": 1 }"#.to_string(),
+        );
+        let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let todo = res_to_todo(res).await;
+        assert_eq!(1, todo.position);
+
+        let req = build_todo_req_with_empty(Method::GET, "/todos");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let todos: Vec<Todo> = serde_json::from_slice(&bytes).unwrap();
+        let ids: Vec<i32> = todos.iter().map(|todo| todo.id).collect();
+        assert_eq!(vec![3, 1, 2], ids);
+    }
+
+    #[tokio::test]
+    async fn should_bump_todo_to_top() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+        let app = create_app(repository, Config::default());
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::POST, "/todos/2/bump")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(1, res_to_todo(res).await.position);
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        let ids: Vec<i32> = res_to_todos(res).await.iter().map(|todo| todo.id).collect();
+        assert_eq!(vec![2, 1, 3], ids);
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::POST, "/todos/9/bump")).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_bump_todo_to_top_of_descending_order() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+        let app = create_app(
+            repository,
+            Config {
+                default_sort_order: SortOrder::Desc,
+                ..Config::default()
+            },
+        );
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::POST, "/todos/2/bump")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(3, res_to_todo(res).await.position);
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        let ids: Vec<i32> = res_to_todos(res).await.iter().map(|todo| todo.id).collect();
+        assert_eq!(vec![2, 3, 1], ids);
+    }
+
+    #[tokio::test]
+    async fn should_complete_and_uncomplete_all_todos() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 3).await;
+
+        for (path, completed) in [("/todos/complete-all", true), ("/todos/uncomplete-all", false)] {
+            let req = build_todo_req_with_empty(Method::POST, path);
+            let res = create_app(repository.clone(), Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status());
+            let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(serde_json::json!({ "affected": 3 }), body);
+
+            let todos = repository.all().await.unwrap();
+            assert!(todos.iter().all(|todo| todo.completed == completed), "{}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_replace_text_across_todos() {
+        let repository = TodoRepositoryForMemory::new();
+        for text in ["buy milk", "buy bread", "sell car"] {
+            repository.create(CreateTodo::new(text.to_string())).await.unwrap();
+        }
+        let app = create_app(repository.clone(), Config::default());
+        let replace = |body: &str| build_todo_req_with_json("/todos/replace-text", Method::POST, body.to_string());
+
+        let res = app.clone().oneshot(replace(r#"{ "from": "buy", "to": "get" }"#)).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(serde_json::json!({ "affected": 2 }), serde_json::from_slice::<serde_json::Value>(&bytes).unwrap());
+        let texts: Vec<String> = repository.all().await.unwrap().into_iter().map(|todo| todo.text).collect();
+        assert_eq!(vec!["get milk", "get bread", "sell car"], texts);
+
+        let res = app.clone().oneshot(replace(r#"{ "from": "", "to": "x" }"#)).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+
+        let res = app.clone().oneshot(replace(r#"{ "from": "get milk", "to": "get bread" }"#)).await.unwrap();
+        assert_eq!(StatusCode::CONFLICT, res.status());
+        assert_eq!("get milk", repository.find(1).await.unwrap().text);
+
+        // emptying a todo or pushing it past the limit is refused for the whole batch
+        let res = app.clone().oneshot(replace(r#"{ "from": "sell car", "to": "" }"#)).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let long = "x".repeat(Config::default().max_todo_text_len);
+        let res = app.oneshot(replace(&format!(r#"{{ "from": "get", "to": "{}" }}"#, long))).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        let texts: Vec<String> = repository.all().await.unwrap().into_iter().map(|todo| todo.text).collect();
+        assert_eq!(vec!["get milk", "get bread", "sell car"], texts);
+        let history = repository.history(1).await.unwrap();
+        assert_eq!(AuditLog::UPDATE, history.last().unwrap().action);
+    }
+
+    #[tokio::test]
+    async fn should_save_find_and_commit_draft() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(repository.clone(), Config::default());
+
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1/draft");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+
+        let req = build_todo_req_with_json(
+            "/todos/1/draft",
+            Method::PUT,
+            r#"{ "text": "draft text" }"#.to_string(),
+        );
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
+        // drafts never touch the store until committed
+        assert_eq!("todo 0", repository.find(1).await.unwrap().text);
+
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1/draft");
+        let res = app.clone().oneshot(req).await.unwrap();
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let draft: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!({ "text": "draft text" }), draft);
+
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/draft/commit");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let todo = res_to_todo(res).await;
+        assert_eq!("draft text", todo.text);
+
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/draft/commit");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+
+        // an emptied draft can be saved but not committed, and survives the attempt
+        let req = build_todo_req_with_json("/todos/1/draft", Method::PUT, r#"{ "text": "" }"#.to_string());
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NO_CONTENT, res.status());
+        let req = build_todo_req_with_empty(Method::POST, "/todos/1/draft/commit");
+        let res = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, res.status());
+        assert_eq!("draft text", repository.find(1).await.unwrap().text);
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1/draft");
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_expire_draft_after_ttl() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let config = Config {
+            draft_ttl_secs: 0,
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+
+        let req = build_todo_req_with_json(
+            "/todos/1/draft",
+            Method::PUT,
+            r#"{ "text": "draft text" }"#.to_string(),
+        );
+        app.clone().oneshot(req).await.unwrap();
+
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1/draft");
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_answer_cors_preflight_with_max_age() {
+        let repository = TodoRepositoryForMemory::new();
+        let config = Config {
+            cors_max_age_secs: 600,
+            ..Config::default()
+        };
+        let req = Request::builder()
+            .uri("/todos")
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "http://example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .body(Body::empty())
+            .unwrap();
+        let res = create_app(repository, config).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!("600", res.headers()[header::ACCESS_CONTROL_MAX_AGE]);
+    }
+
+    #[tokio::test]
+    async fn should_answer_500_json_when_handler_panics() {
+        let app = Router::new()
+            .route("/panic", get(|| async { panic!("deliberate panic") as StatusCode }))
+            .layer(CatchPanicLayer::custom(panic_response));
+        let req = build_todo_req_with_empty(Method::GET, "/panic");
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(serde_json::json!({ "error": "internal server error" }), body);
+    }
+
+    #[tokio::test]
+    async fn should_map_repository_errors_to_status() {
+        let cases = [
+            ("create", RepositoryError::Unexpected("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
+            ("create", RepositoryError::Conflict("todos_pkey".to_string()), StatusCode::CONFLICT),
+            ("find", RepositoryError::NotFound(1), StatusCode::NOT_FOUND),
+            ("exists", RepositoryError::Unexpected("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
+            ("page", RepositoryError::Unexpected("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
+            ("all", RepositoryError::Unexpected("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
+            ("update", RepositoryError::NotFound(1), StatusCode::NOT_FOUND),
+            ("update", RepositoryError::BadRequest("fk".to_string()), StatusCode::BAD_REQUEST),
+            ("duplicate", RepositoryError::NotFound(1), StatusCode::NOT_FOUND),
+            ("reorder", RepositoryError::NotFound(1), StatusCode::NOT_FOUND),
+            ("set_all_completed", RepositoryError::Unexpected("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
+            ("import", RepositoryError::Conflict("todos_uuid_key".to_string()), StatusCode::CONFLICT),
+            ("delete", RepositoryError::NotFound(1), StatusCode::NOT_FOUND),
+            ("find", RepositoryError::Unavailable("pool timed out".to_string()), StatusCode::SERVICE_UNAVAILABLE),
+        ];
+        for (method, error, expected) in cases {
+            let req = match method {
+                "create" => build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "x" }"#.to_string()),
+                "find" => build_todo_req_with_empty(Method::GET, "/todos/1"),
+                "exists" => build_todo_req_with_empty(Method::HEAD, "/todos/1"),
+                "page" => build_todo_req_with_empty(Method::GET, "/todos"),
+                "all" => build_todo_req_with_empty(Method::GET, "/todos/board"),
+                "update" => build_todo_req_with_json("/todos/1", Method::PATCH, r#"{ "text": "x" }"#.to_string()),
+                "duplicate" => build_todo_req_with_empty(Method::POST, "/todos/1/duplicate"),
+                "reorder" => build_todo_req_with_json("/todos/1/reorder", Method::POST, r#"{ "position": 1 }"#.to_string()),
+                "set_all_completed" => build_todo_req_with_empty(Method::POST, "/todos/complete-all"),
+                "import" => build_todo_req_with_json(
+                    "/todos/import",
+                    Method::POST,
+                    r#"{ "schema_version": 1, "todos": [] }"#.to_string(),
+                ),
+                "delete" => build_todo_req_with_empty(Method::DELETE, "/todos/1"),
+                _ => unreachable!(),
+            };
+            let repository = MockTodoRepository::new().fail(method, error);
+            let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+            assert_eq!(expected, res.status(), "{}", method);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_serialize_requests_beyond_concurrency_limit() {
+        let delay = Duration::from_millis(200);
+        let repository = MockTodoRepository::new().slow("find", delay);
+        let config = Config {
+            max_concurrent_requests: 1,
+            ..Config::default()
+        };
+        let app = create_app(repository, config);
+
+        let started = std::time::Instant::now();
+        let (first, second) = tokio::join!(
+            app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos/1")),
+            app.oneshot(build_todo_req_with_empty(Method::GET, "/todos/1")),
+        );
+        first.unwrap();
+        second.unwrap();
+        assert!(started.elapsed() >= delay * 2, "finished in {:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn should_answer_500_when_repository_panics() {
+        let repository = MockTodoRepository::new().panic("find", "repository exploded");
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1");
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_serve_http2_prior_knowledge() {
+        let app = create_app(TodoRepositoryForMemory::new(), Config::default());
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let client = hyper::Client::builder().http2_only(true).build_http::<Body>();
+        let uri = format!("http://{}/todos", addr).parse().unwrap();
+        let res = client.get(uri).await.expect("h2c request failed");
+        assert_eq!(hyper::Version::HTTP_2, res.version());
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn should_serve_versioned_and_deprecated_paths() {
+        let logs = LogBuffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 1).await;
+        let app = create_app(repository, Config::default());
+
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/v1/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(1, res_to_todos(res).await.len());
+        assert!(!String::from_utf8(logs.0.lock().unwrap().clone()).unwrap().contains("deprecated"));
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(1, res_to_todos(res).await.len());
+        let logged = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains("deprecated unversioned path /todos"), "{}", logged);
+    }
+
+    #[tokio::test]
+    async fn should_record_history_of_mutations() {
+        let repository = TodoRepositoryForMemory::new();
+        let app = create_app(repository, Config::default());
+        let req = build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "first" }"#.to_string());
+        assert_eq!(StatusCode::CREATED, app.clone().oneshot(req).await.unwrap().status());
+        let req = build_todo_req_with_json("/todos/1", Method::PATCH, r#"{ "text": "second" }"#.to_string());
+        app.clone().oneshot(req).await.unwrap();
+
+        let req = build_todo_req_with_empty(Method::GET, "/todos/1/history");
+        let res = app.oneshot(req).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let history: Vec<AuditLog> = serde_json::from_slice(&bytes).unwrap();
+        let actions = history.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec![AuditLog::CREATE, AuditLog::UPDATE], actions);
+        assert_eq!("first", history[0].payload_json["text"]);
+        assert_eq!("second", history[1].payload_json["text"]);
+        assert!(history[0].at <= history[1].at);
+    }
+
+    #[cfg(feature = "dev-admin")]
+    #[tokio::test]
+    async fn should_count_requests_per_route() {
+        let repository = TodoRepositoryForMemory::new();
+        create_todos(&repository, 2).await;
+        let app = create_app(repository, Config::default());
+        for path in ["/todos", "/todos", "/todos", "/v1/todos", "/todos/1", "/todos/2"] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!(StatusCode::OK, res.status(), "{}", path);
+        }
+        for path in ["/nope/1", "/nope/2"] {
+            let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, path)).await.unwrap();
+            assert_eq!(StatusCode::NOT_FOUND, res.status(), "{}", path);
+        }
+
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/admin/stats")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let stats: std::collections::HashMap<String, u64> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(Some(&3), stats.get("GET /todos"));
+        assert_eq!(Some(&1), stats.get("GET /v1/todos"));
+        assert_eq!(Some(&2), stats.get("GET /todos/:id"));
+        assert_eq!(Some(&2), stats.get("GET <unmatched>"), "{:?}", stats);
+        assert!(stats.keys().all(|key| !key.contains("/nope")), "{:?}", stats);
+    }
+
+    #[cfg(feature = "dev-admin")]
+    #[tokio::test]
+    async fn should_toggle_maintenance_mode_at_runtime() {
+        let app = create_app(TodoRepositoryForMemory::new(), Config::default());
+        let toggle = |enabled: bool| {
+            build_todo_req_with_json("/admin/maintenance", Method::PUT, format!(r#"{{ "enabled": {} }}"#, enabled))
+        };
+
+        let res = app.clone().oneshot(toggle(true)).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+        let res = app.clone().oneshot(build_todo_req_with_empty(Method::GET, "/health")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        let res = app.clone().oneshot(toggle(false)).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let res = app.oneshot(build_todo_req_with_empty(Method::GET, "/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[cfg(feature = "dev-admin")]
+    #[tokio::test]
+    async fn should_toggle_read_only_mode_at_runtime() {
+        let app = create_app(TodoRepositoryForMemory::new(), Config::default());
+        let toggle = |enabled: bool| {
+            build_todo_req_with_json("/admin/read-only", Method::PUT, format!(r#"{{ "enabled": {} }}"#, enabled))
+        };
+        let create = || build_todo_req_with_json("/todos", Method::POST, r#"{ "text": "write" }"#.to_string());
+
+        let res = app.clone().oneshot(toggle(true)).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let res = app.clone().oneshot(create()).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+
+        let res = app.clone().oneshot(toggle(false)).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let res = app.oneshot(create()).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+
+    #[tokio::test]
+    async fn should_delete_todo() {
+        let repository = TodoRepositoryForMemory::new();
+        repository.
             create(CreateTodo::new("before_update_todo".to_string()))
             .await
             .expect("failed create todo");
         let req = build_todo_req_with_empty(Method::DELETE, "/todos/1");
-        let res = create_app(repository).oneshot(req).await.unwrap();
+        let res = create_app(repository, Config::default()).oneshot(req).await.unwrap();
         assert_eq!(StatusCode::NO_CONTENT, res.status());
     }
+
+    // Whatever bytes arrive, the create path must answer with a clean status instead of panicking.
+    fn create_status(body: Vec<u8>) -> StatusCode {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let repository = TodoRepositoryForMemory::new();
+            let req = Request::builder()
+                .uri("/todos")
+                .method(Method::POST)
+                .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .body(Body::from(body))
+                .unwrap();
+            create_app(repository, Config::default())
+                .oneshot(req)
+                .await
+                .unwrap()
+                .status()
+        })
+    }
+
+    #[test]
+    fn create_todo_rejects_deeply_nested_json() {
+        let body = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+        assert_eq!(StatusCode::BAD_REQUEST, create_status(body.into_bytes()));
+    }
+
+    proptest! {
+        #[test]
+        fn create_todo_never_panics_on_arbitrary_bytes(body in prop::collection::vec(any::<u8>(), 0..256)) {
+            let status = create_status(body);
+            prop_assert!(
+                [StatusCode::CREATED, StatusCode::BAD_REQUEST, StatusCode::UNPROCESSABLE_ENTITY].contains(&status),
+                "unexpected status {}", status
+            );
+        }
+
+        #[test]
+        fn create_todo_never_panics_on_arbitrary_json(body in r#"\{ ?("text"|"uuid"|"completed"|[a-z]{0,8}) ?: ?(-?[0-9]{1,20}|"[^"\\]{0,120}"|true|null|\[\]|\{\}) ?,? ?\}?"#) {
+            let status = create_status(body.into_bytes());
+            prop_assert!(
+                [StatusCode::CREATED, StatusCode::BAD_REQUEST, StatusCode::UNPROCESSABLE_ENTITY].contains(&status),
+                "unexpected status {}", status
+            );
+        }
+
+        #[test]
+        fn create_todo_validates_arbitrary_text(text in any::<String>()) {
+            let body = serde_json::json!({ "text": text }).to_string();
+            let status = create_status(body.into_bytes());
+            let has_control = text.chars().any(|c| c.is_ascii_control() && c != '\t');
+            let expected = if (1..=100).contains(&text.chars().count()) && !has_control {
+                StatusCode::CREATED
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            prop_assert_eq!(expected, status);
+        }
+    }
 }
@@ -0,0 +1,197 @@
+use axum::{
+    body::Body,
+    extract::Extension,
+    http::{header, Method, Request, StatusCode},
+    response::{Headers, IntoResponse, Response},
+    routing::Route,
+    Json, Router,
+};
+#[cfg(feature = "dev-admin")]
+use serde::Deserialize;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tower::{layer::layer_fn, service_fn, ServiceExt};
+
+use crate::repositories::TodoRepository;
+
+// Deploys and migrations usually finish within a minute, so that is when clients should retry.
+const RETRY_AFTER_SECS: u64 = 60;
+
+// One flag shared by the guard and the admin toggle, so a flip applies from the next request on.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceMode(Arc<AtomicBool>);
+
+impl MaintenanceMode {
+    pub fn new(enabled: bool) -> Self {
+        MaintenanceMode(Arc::new(AtomicBool::new(enabled)))
+    }
+
+    #[cfg(any(test, feature = "dev-admin"))]
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Same toggle as `MaintenanceMode`, but only writes are turned away, e.g. while a backup runs.
+#[derive(Debug, Clone, Default)]
+pub struct ReadOnlyMode(Arc<AtomicBool>);
+
+impl ReadOnlyMode {
+    pub fn new(enabled: bool) -> Self {
+        ReadOnlyMode(Arc::new(AtomicBool::new(enabled)))
+    }
+
+    #[cfg(any(test, feature = "dev-admin"))]
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Only guards the routes already on `router`; anything added afterwards (`/health`, admin) stays up.
+pub fn guard(router: Router, mode: MaintenanceMode) -> Router {
+    router.layer(layer_fn(move |inner: Route| {
+        let mode = mode.clone();
+        service_fn(move |req: Request<Body>| {
+            let inner = inner.clone();
+            let enabled = mode.is_enabled();
+            async move {
+                if enabled {
+                    return Ok(unavailable());
+                }
+                inner.oneshot(req).await
+            }
+        })
+    }))
+}
+
+// Like `guard`, but lets safe methods through so clients can keep reading.
+pub fn read_only_guard(router: Router, mode: ReadOnlyMode) -> Router {
+    router.layer(layer_fn(move |inner: Route| {
+        let mode = mode.clone();
+        service_fn(move |req: Request<Body>| {
+            let inner = inner.clone();
+            let rejected = mode.is_enabled() && !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+            async move {
+                if rejected {
+                    return Ok(read_only());
+                }
+                inner.oneshot(req).await
+            }
+        })
+    }))
+}
+
+fn read_only() -> Response {
+    let body = serde_json::json!({ "error": "service is read-only" });
+    let retry_after = Headers([(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())]);
+    (StatusCode::SERVICE_UNAVAILABLE, retry_after, Json(body)).into_response()
+}
+
+fn unavailable() -> Response {
+    let body = serde_json::json!({ "error": "service under maintenance" });
+    let retry_after = Headers([(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())]);
+    (StatusCode::SERVICE_UNAVAILABLE, retry_after, Json(body)).into_response()
+}
+
+pub async fn health() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+// Unlike `/health`, this asks whether traffic should be sent here: not before the schema is current.
+pub async fn ready<T: TodoRepository>(Extension(repository): Extension<Arc<T>>) -> Response {
+    match repository.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => Json(serde_json::json!({ "status": "ready" })).into_response(),
+        Ok(pending) => {
+            let body = serde_json::json!({ "status": "not ready", "pending_migrations": pending });
+            (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+        }
+        Err(e) => {
+            tracing::error!("readiness check failed: {:#}", e);
+            let body = serde_json::json!({ "status": "not ready" });
+            (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response()
+        }
+    }
+}
+
+#[cfg(feature = "dev-admin")]
+#[derive(Debug, Deserialize)]
+pub struct MaintenancePayload {
+    enabled: bool,
+}
+
+#[cfg(feature = "dev-admin")]
+pub async fn toggle(
+    Json(payload): Json<MaintenancePayload>,
+    Extension(mode): Extension<MaintenanceMode>,
+) -> impl IntoResponse {
+    mode.set(payload.enabled);
+    Json(serde_json::json!({ "enabled": mode.is_enabled() }))
+}
+
+#[cfg(feature = "dev-admin")]
+pub async fn toggle_read_only(
+    Json(payload): Json<MaintenancePayload>,
+    Extension(mode): Extension<ReadOnlyMode>,
+) -> impl IntoResponse {
+    mode.set(payload.enabled);
+    Json(serde_json::json!({ "enabled": mode.is_enabled() }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::routing::get;
+
+    fn request(path: &str) -> Request<Body> {
+        Request::builder().uri(path).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn guard_follows_the_flag() {
+        let mode = MaintenanceMode::default();
+        let app = guard(Router::new().route("/todos", get(|| async { "todos" })), mode.clone())
+            .route("/health", get(health));
+
+        let res = app.clone().oneshot(request("/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        mode.set(true);
+        let res = app.clone().oneshot(request("/todos")).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+        assert_eq!("60", res.headers()[header::RETRY_AFTER]);
+        let res = app.clone().oneshot(request("/health")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+
+        mode.set(false);
+        let res = app.oneshot(request("/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+    }
+
+    #[tokio::test]
+    async fn read_only_guard_rejects_only_writes() {
+        let mode = ReadOnlyMode::new(true);
+        let routes = Router::new().route("/todos", get(|| async { "todos" }).post(|| async { StatusCode::CREATED }));
+        let app = read_only_guard(routes, mode.clone());
+        let post = || Request::post("/todos").body(Body::empty()).unwrap();
+
+        let res = app.clone().oneshot(request("/todos")).await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        let res = app.clone().oneshot(post()).await.unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, res.status());
+        assert_eq!("60", res.headers()[header::RETRY_AFTER]);
+
+        mode.set(false);
+        let res = app.oneshot(post()).await.unwrap();
+        assert_eq!(StatusCode::CREATED, res.status());
+    }
+}
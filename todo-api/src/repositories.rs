@@ -1,15 +1,70 @@
 use axum::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{future::BoxFuture, stream::BoxStream, TryStreamExt};
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use std::{collections::HashMap, str::FromStr};
 use thiserror::Error;
-use validator::Validate;
-use sqlx::{FromRow, PgPool};
+use validator::{Validate, ValidationError};
+use sqlx::{migrate::Migrator, Executor, FromRow, PgConnection, PgPool, Postgres, Transaction};
+use uuid::Uuid;
 
-#[derive(Debug, Error)]
-enum RepositoryError {
+use crate::util::{flexible_bool, text_len};
+
+#[derive(Debug, Clone, Error)]
+pub enum RepositoryError {
     #[error("Unexpected Error: [{0}]")]
     Unexpected(String),
     #[error("NotFound, id is {0}")]
     NotFound(i32),
+    #[error("Duplicate, uuid is {0}")]
+    Duplicate(Uuid),
+    #[error("Conflict: [{0}]")]
+    Conflict(String),
+    #[error("BadRequest: [{0}]")]
+    BadRequest(String),
+    #[error("Unavailable: [{0}]")]
+    Unavailable(String),
+}
+
+// Translates a driver error into a RepositoryError by its Postgres SQLSTATE; an exhausted pool
+// is reported as unavailable so callers can retry.
+pub fn map_db_error(e: sqlx::Error) -> RepositoryError {
+    match e {
+        sqlx::Error::Database(ref db) => match db.code().as_deref() {
+            // unique_violation
+            Some("23505") => RepositoryError::Conflict(db.message().to_string()),
+            // foreign_key_violation
+            Some("23503") => RepositoryError::BadRequest(db.message().to_string()),
+            _ => RepositoryError::Unexpected(e.to_string()),
+        },
+        sqlx::Error::PoolTimedOut => RepositoryError::Unavailable(e.to_string()),
+        _ => RepositoryError::Unexpected(e.to_string()),
+    }
+}
+
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+// Inclusive id range `around_id ± radius`, saturated so a huge radius can't overflow the i32 ids.
+fn window_bounds(around_id: i32, radius: i64) -> (i32, i32) {
+    let radius = radius.max(0);
+    let clamp = |id: i64| id.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+    (clamp((around_id as i64).saturating_sub(radius)), clamp((around_id as i64).saturating_add(radius)))
+}
+
+// Plain transactions keep Postgres' READ COMMITTED; add levels here as callers need them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    RepeatableRead,
+}
+
+impl IsolationLevel {
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,341 +76,2797 @@ impl TodoRepositoryForDb {
     pub fn new(pool: PgPool) -> Self {
         TodoRepositoryForDb { pool }
     }
-}
 
-#[async_trait]
-pub trait TodoRepository: Clone + std::marker::Send + std::marker::Sync + 'static {
-    async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo>;
-    async fn find(&self, id: i32) -> anyhow::Result<Todo>;
-    async fn all(&self) -> anyhow::Result<Vec<Todo>>;
-    async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo>;
-    async fn delete(&self, id: i32) -> anyhow::Result<()>;
-}
+    // Runs `f` inside one transaction: committed when it returns Ok, rolled back on Err.
+    pub async fn with_transaction<R, F>(&self, f: F) -> anyhow::Result<R>
+    where
+        R: Send,
+        F: for<'t> FnOnce(&'t mut Transaction<'static, Postgres>) -> BoxFuture<'t, anyhow::Result<R>>
+            + Send,
+    {
+        self.run_transaction(None, f).await
+    }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, FromRow)]
-pub struct Todo {
-    pub id: i32,
-    pub text: String,
-    pub completed: bool,
-}
+    // Like `with_transaction`, but every statement in `f` sees the same data under `isolation`.
+    pub async fn with_isolation<R, F>(&self, isolation: IsolationLevel, f: F) -> anyhow::Result<R>
+    where
+        R: Send,
+        F: for<'t> FnOnce(&'t mut Transaction<'static, Postgres>) -> BoxFuture<'t, anyhow::Result<R>>
+            + Send,
+    {
+        self.run_transaction(Some(isolation), f).await
+    }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
-pub struct CreateTodo {
-    #[validate(length(min = 1, message = "Can not be empty."))]
-    #[validate(length(max = 100, message = "Over text length"))]
-    text: String,
-}
+    async fn run_transaction<R, F>(&self, isolation: Option<IsolationLevel>, f: F) -> anyhow::Result<R>
+    where
+        R: Send,
+        F: for<'t> FnOnce(&'t mut Transaction<'static, Postgres>) -> BoxFuture<'t, anyhow::Result<R>>
+            + Send,
+    {
+        let mut transaction = self.pool.begin().await.map_err(map_db_error)?;
+        if let Some(isolation) = isolation {
+            // must run before the first query; the level cannot be a bind parameter
+            let statement = format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql());
+            transaction.execute(statement.as_str()).await.map_err(map_db_error)?;
+        }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
-pub struct UpdateTodo {
-    #[validate(length(min = 1, message = "Can not be empty."))]
-    #[validate(length(max = 100, message = "Over text length"))]
-    text: Option<String>,
-    completed: Option<bool>,
-}
+        match f(&mut transaction).await {
+            Ok(result) => {
+                transaction.commit().await.map_err(map_db_error)?;
+                Ok(result)
+            }
+            Err(e) => {
+                transaction.rollback().await.map_err(map_db_error)?;
+                Err(e)
+            }
+        }
+    }
 
-#[async_trait]
-impl TodoRepository for TodoRepositoryForDb {
-    async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo> {
-        let mut transaction = self.pool
-            .begin()
+    // Versions `migrator` knows that have no successful row in `_sqlx_migrations`; a database that
+    // was never migrated has no such table and is behind on all of them.
+    pub async fn pending_in(&self, migrator: &Migrator) -> anyhow::Result<Vec<i64>> {
+        // a runtime query, since the checked macros can't assume the table exists at build time
+        let applied: Vec<i64> = match sqlx::query_scalar(include_str!("../sql/appliedMigrations.sql"))
+            .fetch_all(&self.pool)
             .await
-            .unwrap();
+        {
+            Ok(applied) => applied,
+            // undefined_table
+            Err(sqlx::Error::Database(ref db)) if db.code().as_deref() == Some("42P01") => Vec::new(),
+            Err(e) => return Err(map_db_error(e).into()),
+        };
+
+        Ok(migrator
+            .iter()
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect())
+    }
 
+    pub async fn create_with(conn: &mut PgConnection, payload: CreateTodo) -> anyhow::Result<Todo> {
+        let uuid = payload.uuid.unwrap_or_else(Uuid::new_v4);
         let todo = sqlx::query_file_as!(
                 Todo,
                 "sql/insertTodo.sql",
+                uuid,
                 payload.text.clone()
             )
-            .fetch_one(&mut transaction)
+            .fetch_one(&mut *conn)
             .await
-            .unwrap_or_else(|_| {
-                panic!("Failed to create todo.")
-            });
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.constraint() == Some("todos_uuid_key") => {
+                    RepositoryError::Duplicate(uuid)
+                }
+                _ => map_db_error(e),
+            })?;
+        Self::audit_with(&mut *conn, AuditLog::CREATE, &todo).await?;
+
+        Ok(todo)
+    }
 
-        transaction
-            .commit()
+    // `DO NOTHING` waits out a concurrent insert of the same text, so the follow-up read finds
+    // the winner instead of racing it.
+    pub async fn create_if_absent_with(conn: &mut PgConnection, payload: CreateTodo) -> anyhow::Result<CreatedIfAbsent> {
+        let uuid = payload.uuid.unwrap_or_else(Uuid::new_v4);
+        let inserted = sqlx::query_file_as!(
+                Todo,
+                "sql/insertTodoIfAbsent.sql",
+                uuid,
+                payload.text.clone()
+            )
+            .fetch_optional(&mut *conn)
             .await
-            .unwrap_or_else(|_| {
-                panic!("Commit failed.")
-            });
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.constraint() == Some("todos_uuid_key") => {
+                    RepositoryError::Duplicate(uuid)
+                }
+                _ => map_db_error(e),
+            })?;
+        if let Some(todo) = inserted {
+            Self::audit_with(&mut *conn, AuditLog::CREATE, &todo).await?;
+            return Ok(CreatedIfAbsent::Created(todo));
+        }
 
-        Ok(todo)
+        let existing = sqlx::query_file_as!(
+                Todo,
+                "sql/findOpenTodoByText.sql",
+                payload.text
+            )
+            .fetch_optional(&mut *conn)
+            .await.map_err(map_db_error)?
+            // completed between the two statements; the caller may simply retry
+            .ok_or_else(|| RepositoryError::Conflict(format!("todo {:?} changed concurrently", payload.text)))?;
+
+        Ok(CreatedIfAbsent::Existing(existing))
     }
 
-    async fn find(&self, id: i32) -> anyhow::Result<Todo> {
+    pub async fn find_with(conn: &mut PgConnection, id: i32) -> anyhow::Result<Todo> {
         let todo = sqlx::query_file_as!(
                 Todo,
                 "sql/findTodo.sql",
                 id
             )
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *conn)
             .await
             .map_err(|e| match e {
                 sqlx::Error::RowNotFound => RepositoryError::NotFound(id),
-                _ => RepositoryError::Unexpected(e.to_string()),
+                _ => map_db_error(e),
             })?;
 
         Ok(todo)
     }
 
-    async fn all(&self) -> anyhow::Result<Vec<Todo>> {
+    pub async fn update_with(conn: &mut PgConnection, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo> {
+        // `COALESCE` keeps omitted fields in the same statement, so there is no read to race with.
         let todo = sqlx::query_file_as!(
                 Todo,
-                "sql/allTodo.sql"
+                "sql/updateTodo.sql",
+                payload.text,
+                payload.completed,
+                id
             )
-            .fetch_all(&self.pool)
-            .await?;
-    
+            .fetch_optional(&mut *conn)
+            .await.map_err(map_db_error)?
+            .ok_or(RepositoryError::NotFound(id))?;
+        Self::audit_with(&mut *conn, AuditLog::UPDATE, &todo).await?;
+
         Ok(todo)
     }
 
-    async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo> {
-        let mut transaction = self.pool
-            .begin()
-            .await
-            .unwrap();
+    // `xmax` is only zero on a freshly inserted row, which tells an insert from a conflict update.
+    pub async fn upsert_with(conn: &mut PgConnection, uuid: Uuid, payload: UpsertTodo) -> anyhow::Result<Upserted> {
+        let row = sqlx::query_file!(
+                "sql/upsertTodo.sql",
+                uuid,
+                payload.text,
+                payload.completed
+            )
+            .fetch_one(&mut *conn)
+            .await.map_err(map_db_error)?;
+        let todo = Todo {
+            id: row.id,
+            text: row.text,
+            completed: row.completed,
+            uuid: row.uuid,
+            position: row.position,
+            updated_at: row.updated_at,
+            archived: row.archived,
+        };
 
-        let old_todo = self.find(id).await?;
+        if row.inserted {
+            Self::audit_with(&mut *conn, AuditLog::CREATE, &todo).await?;
+            Ok(Upserted::Created(todo))
+        } else {
+            Self::audit_with(&mut *conn, AuditLog::UPDATE, &todo).await?;
+            Ok(Upserted::Updated(todo))
+        }
+    }
 
-        let todo = sqlx::query_file_as!(
+    // The copy keeps the source text verbatim; a suffix could push it past the length limit.
+    // The copy is open, so it takes the first free `(copy n)` name to stay clear of the open text
+    // index; a copy made concurrently under the same name still surfaces as a conflict.
+    pub async fn duplicate_with(conn: &mut PgConnection, id: i32) -> anyhow::Result<Todo> {
+        let source = Self::find_with(&mut *conn, id).await?;
+        let mut n = 1;
+        let text = loop {
+            let text = copy_text(&source.text, n);
+            let taken = sqlx::query_file_as!(
+                    Todo,
+                    "sql/findOpenTodoByText.sql",
+                    text
+                )
+                .fetch_optional(&mut *conn)
+                .await.map_err(map_db_error)?;
+            if taken.is_none() {
+                break text;
+            }
+            n += 1;
+        };
+        Self::create_with(&mut *conn, CreateTodo { text, uuid: None }).await
+    }
+
+    pub async fn reorder_with(conn: &mut PgConnection, id: i32, position: i32) -> anyhow::Result<Reordered> {
+        let todo = Self::find_with(&mut *conn, id).await?;
+
+        let max_position = sqlx::query_file_scalar!("sql/maxTodoPosition.sql")
+            .fetch_one(&mut *conn)
+            .await.map_err(map_db_error)?;
+        let position = position.clamp(1, max_position);
+
+        // rows between the old and new slot shift by one so positions stay contiguous
+        let (mut moved, mut shifted): (Vec<Todo>, Vec<Todo>) = sqlx::query_file_as!(
                 Todo,
-                "sql/updateTodo.sql",
-                payload.text.unwrap_or(old_todo.text),
-                payload.completed.unwrap_or(old_todo.completed),
-                id
+                "sql/reorderTodo.sql",
+                id,
+                todo.position,
+                position
             )
-            .fetch_one(&mut transaction)
-            .await
-            .unwrap_or_else(|_| {
-                panic!("Failed to update todo.")
-            });
+            .fetch_all(&mut *conn)
+            .await.map_err(map_db_error)?
+            .into_iter()
+            .partition(|todo| todo.id == id);
+        shifted.sort_by_key(|todo| (todo.position, todo.id));
 
-        transaction
-            .commit()
-            .await
-            .unwrap_or_else(|_| {
-                panic!("Commit failed.")
-            });
+        let todo = moved.pop().ok_or(RepositoryError::NotFound(id))?;
+        for todo in std::iter::once(&todo).chain(&shifted) {
+            Self::audit_with(&mut *conn, AuditLog::UPDATE, todo).await?;
+        }
 
-        Ok(todo)
+        Ok(Reordered { todo, shifted })
     }
 
-    async fn delete(&self, id: i32) -> anyhow::Result<()> {
-        let mut transaction = self.pool
-            .begin()
-            .await
-            .unwrap();
+    pub async fn swap_with(conn: &mut PgConnection, id_a: i32, id_b: i32) -> anyhow::Result<(Todo, Todo)> {
+        let a = Self::find_with(&mut *conn, id_a).await?;
+        let b = Self::find_with(&mut *conn, id_b).await?;
+
+        sqlx::query_file!(
+                "sql/swapTodoPositions.sql",
+                a.id,
+                b.id,
+                a.position,
+                b.position
+            )
+            .execute(&mut *conn)
+            .await.map_err(map_db_error)?;
+
+        let swapped = (Todo { position: b.position, ..a }, Todo { position: a.position, ..b });
+        Self::audit_with(&mut *conn, AuditLog::UPDATE, &swapped.0).await?;
+        Self::audit_with(&mut *conn, AuditLog::UPDATE, &swapped.1).await?;
+
+        Ok(swapped)
+    }
 
-        sqlx::query_file_as!(
+    pub async fn set_all_completed_with(conn: &mut PgConnection, completed: bool) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
                 Todo,
-                "sql/deleteTodo.sql",
-                id
+                "sql/setAllTodoCompleted.sql",
+                completed
             )
-            .execute(&mut transaction)
+            .fetch_all(&mut *conn)
             .await
-            .map_err(|e| match e {
-                sqlx::Error::RowNotFound => RepositoryError::NotFound(id),
-                _ => RepositoryError::Unexpected(e.to_string()),
-            })?;
+            .map_err(map_db_error)?;
 
-        transaction
-            .commit()
-            .await
-            .unwrap_or_else(|_| {
-                panic!("Commit failed.")
-            });
+        for todo in &todos {
+            Self::audit_with(&mut *conn, AuditLog::UPDATE, todo).await?;
+        }
 
-        Ok(())
+        Ok(todos)
     }
-}
 
-#[cfg(test)]
-#[cfg(feature = "database-test")]
-mod test {
-    use super::*;
-    use dotenv::dotenv;
-    use sqlx::PgPool;
-    use std::env;
+    pub async fn archive_completed_with(conn: &mut PgConnection, updated_before: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/archiveCompletedTodo.sql",
+                updated_before
+            )
+            .fetch_all(&mut *conn)
+            .await.map_err(map_db_error)?;
 
-    async fn initialization_test_pool() -> PgPool {
-        dotenv().ok();
-        let database_url = env::var("DATABASE_URL")
-            .expect("DATABASE URL MUST BE SET.");
+        for todo in &todos {
+            Self::audit_with(&mut *conn, AuditLog::UPDATE, todo).await?;
+        }
 
-        sqlx::postgres::PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&database_url)
-            .await
-            .unwrap_or_else(|_| {
-                panic!("Failed create connection pool.")
-            })
+        Ok(todos)
     }
 
-    #[tokio::test]
-    async fn crud_scenario() {
-        let pool = initialization_test_pool().await;
+    // Bundle positions only order the import; the table is renumbered afterwards so positions stay
+    // contiguous from 1 whatever the bundle carried.
+    pub async fn import_with(conn: &mut PgConnection, todos: Vec<Todo>) -> anyhow::Result<Vec<Upserted>> {
+        for todo in &todos {
+            validate_text(todo)?;
+        }
 
-        let repositry = TodoRepositoryForDb::new(pool.clone());
-        let todo_text = "[crud_scenario] text";
+        let mut imported = Vec::new();
+        for todo in todos {
+            let inserted = sqlx::query_file_scalar!(
+                    "sql/importTodo.sql",
+                    todo.id,
+                    todo.uuid,
+                    todo.text,
+                    todo.completed,
+                    todo.position,
+                    todo.updated_at,
+                    todo.archived
+                )
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(map_db_error)?;
+            imported.push(if inserted { Upserted::Created(todo) } else { Upserted::Updated(todo) });
+        }
 
-        // create
-        let created = repositry
-            .create(CreateTodo::new(todo_text.to_string()))
+        sqlx::query_file!("sql/syncTodoIdSequence.sql")
+            .fetch_one(&mut *conn)
             .await
-            .expect("[create] returned Err");
-
-        assert_eq!(created.text, todo_text);
-        assert!(!created.completed);
+            .map_err(map_db_error)?;
 
-        // find
-        let todo = repositry
-            .find(created.id)
+        let renumbered = sqlx::query_file_as!(Todo, "sql/renumberTodoPositions.sql")
+            .fetch_all(&mut *conn)
             .await
-            .expect("[find] returned Err");
-        assert_eq!(created, todo);
+            .map_err(map_db_error)?;
+        let imported = Upserted::with_renumbered(imported, renumbered);
+        for upserted in &imported {
+            match upserted {
+                Upserted::Created(todo) => Self::audit_with(&mut *conn, AuditLog::CREATE, todo).await?,
+                Upserted::Updated(todo) => Self::audit_with(&mut *conn, AuditLog::UPDATE, todo).await?,
+            }
+        }
 
-        // all
-        let todos = repositry.all().await.expect("[all] returned Err");
-        let todo = todos.first().unwrap();
-        assert_eq!(created, *todo);
+        Ok(imported)
+    }
 
-        // update
-        let updated_text = "[crud_scenario] update text";
-        let todo = repositry
-            .update(
-                todo.id,
-                UpdateTodo { 
-                    text: Some(updated_text.to_string()),
-                    completed: Some(true) 
-                }
+    pub async fn delete_with(conn: &mut PgConnection, id: i32) -> anyhow::Result<()> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/deleteTodo.sql",
+                id
             )
+            .fetch_one(&mut *conn)
             .await
-            .expect("[update] returned Err");
-        
-        assert_eq!(created.id, todo.id);
-        assert_eq!(todo.text, updated_text);
-
-        // delete
-        repositry
-            .delete(todo.id)
-            .await
-            .expect("[delete] returned Err");
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => RepositoryError::NotFound(id),
+                _ => map_db_error(e),
+            })?;
 
-        let res = repositry.find(created.id).await;
-        assert!(res.is_err());
+        sqlx::query_file!(
+                "sql/closeTodoPositionGap.sql",
+                todo.position
+            )
+            .execute(&mut *conn)
+            .await.map_err(map_db_error)?;
+        Self::audit_with(&mut *conn, AuditLog::DELETE, &todo).await?;
 
-        let todo_rows = sqlx::query_file_as!(
-            Todo,
-            "sql/findTodo.sql",
-            todo.id
-        )
-        .fetch_all(&pool)
-        .await
-        .expect("[delete] todo_labes featch error");
-        
-        assert!(todo_rows.is_empty());
+        Ok(())
     }
-}
-
-#[cfg(test)]
-pub mod test_utils {
-    use anyhow::Context;
-    use axum::async_trait;
-    use std::{
-        collections::HashMap,
-        sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
-    };
 
-    use super::*;
+    // `STRPOS` rather than `LIKE`, so `%` and `_` in `from` match themselves.
+    pub async fn replace_text_with(conn: &mut PgConnection, from: String, to: String) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/replaceTodoText.sql",
+                from,
+                to
+            )
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(map_db_error)?;
 
-    impl Todo {
-        pub fn new(id: i32, text: String) -> Self {
-            Self {
-                id,
-                text,
-                completed: false,
-            }
+        for todo in &todos {
+            validate_text(todo)?;
+            Self::audit_with(&mut *conn, AuditLog::UPDATE, todo).await?;
         }
-    }
 
-    impl CreateTodo {
-        pub fn new(text: String) -> Self {
-            Self { text }
-        }
+        Ok(todos)
     }
 
-    type TodoDatas = HashMap<i32, Todo>;
+    // Written on the mutation's own connection so the entry commits or rolls back with it.
+    pub async fn audit_with(conn: &mut PgConnection, action: &str, todo: &Todo) -> anyhow::Result<()> {
+        let payload = serde_json::to_value(todo)?;
+        sqlx::query_file!(
+                "sql/insertAuditLog.sql",
+                todo.id,
+                action,
+                payload
+            )
+            .execute(&mut *conn)
+            .await.map_err(map_db_error)?;
 
-    #[derive(Debug, Clone)]
-    pub struct TodoRepositoryForMemory {
-        store: Arc<RwLock<TodoDatas>>,
+        Ok(())
     }
+}
 
-    impl TodoRepositoryForMemory {
-        pub fn new() -> Self {
-            TodoRepositoryForMemory {
-                store: Arc::default(),
-            }
-        }
-
-        fn write_store_ref(&self) -> RwLockWriteGuard<TodoDatas> {
-            self.store.write().unwrap()
-        }
-
-        fn read_store_ref(&self) -> RwLockReadGuard<TodoDatas> {
-            self.store.read().unwrap()
-        }
+#[async_trait]
+pub trait TodoRepository: Clone + std::marker::Send + std::marker::Sync + 'static {
+    async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo>;
+    async fn create_if_absent(&self, payload: CreateTodo) -> anyhow::Result<CreatedIfAbsent>;
+    async fn find(&self, id: i32) -> anyhow::Result<Todo>;
+    async fn find_by_uuid(&self, uuid: Uuid) -> anyhow::Result<Option<Todo>>;
+    async fn find_many_by_uuid(&self, uuids: &[Uuid]) -> anyhow::Result<Vec<Todo>>;
+    async fn find_by_text(&self, text: &str) -> anyhow::Result<Vec<Todo>>;
+    async fn exists(&self, id: i32) -> anyhow::Result<bool>;
+    async fn count_where(&self, filter: TodoFilter) -> anyhow::Result<i64>;
+    async fn progress(&self) -> anyhow::Result<TodoProgress>;
+    async fn all(&self) -> anyhow::Result<Vec<Todo>>;
+    async fn snapshot(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<TodoSnapshot>;
+    async fn page(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<Vec<Todo>>;
+    async fn window(&self, around_id: i32, radius: i64) -> anyhow::Result<Vec<Todo>>;
+    async fn latest(&self) -> anyhow::Result<Option<Todo>>;
+    async fn oldest_open(&self) -> anyhow::Result<Option<Todo>>;
+    async fn top_open(&self, n: i64) -> anyhow::Result<Vec<Todo>>;
+    async fn sample(&self, n: i64) -> anyhow::Result<Vec<Todo>>;
+    async fn modified_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<Todo>>;
+    async fn completed_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> anyhow::Result<Vec<Todo>>;
+    async fn deleted_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<i32>>;
+    async fn revision(&self) -> anyhow::Result<i64>;
+    fn stream_all(&self) -> BoxStream<'static, anyhow::Result<Todo>>;
+    async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo>;
+    async fn duplicate(&self, id: i32) -> anyhow::Result<Todo>;
+    async fn upsert(&self, uuid: Uuid, payload: UpsertTodo) -> anyhow::Result<Upserted>;
+    async fn reorder(&self, id: i32, position: i32) -> anyhow::Result<Reordered>;
+    // Moves the todo to whichever end lists first under `order`; `reorder` clamps the position and
+    // shifts the rest in the same transaction.
+    async fn bump(&self, id: i32, order: SortOrder) -> anyhow::Result<Reordered> {
+        let position = match order {
+            SortOrder::Asc => 1,
+            SortOrder::Desc => i32::MAX,
+        };
+        self.reorder(id, position).await
+    }
+    async fn swap(&self, id_a: i32, id_b: i32) -> anyhow::Result<(Todo, Todo)>;
+    async fn set_all_completed(&self, completed: bool) -> anyhow::Result<Vec<Todo>>;
+    async fn replace_text(&self, from: &str, to: &str) -> anyhow::Result<Vec<Todo>>;
+    async fn archive_completed(&self, updated_before: DateTime<Utc>) -> anyhow::Result<Vec<Todo>>;
+    async fn import(&self, todos: Vec<Todo>) -> anyhow::Result<Vec<Upserted>>;
+    async fn delete(&self, id: i32) -> anyhow::Result<()>;
+    async fn history(&self, id: i32) -> anyhow::Result<Vec<AuditLog>>;
+    async fn pending_migrations(&self) -> anyhow::Result<Vec<i64>>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, FromRow)]
+pub struct Todo {
+    #[serde(with = "crate::util::id_format")]
+    pub id: i32,
+    pub text: String,
+    pub completed: bool,
+    pub uuid: Uuid,
+    pub position: i32,
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+impl Todo {
+    pub const FIELDS: &[&str] = &["id", "text", "completed", "uuid", "position", "updated_at", "archived"];
+
+    pub fn new(id: i32, text: String) -> Self {
+        Self {
+            id,
+            text,
+            completed: false,
+            uuid: Uuid::nil(),
+            position: 0,
+            updated_at: DateTime::default(),
+            archived: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateTodo {
+    #[validate(length(min = 1, message = "Can not be empty."))]
+    #[validate(custom = "text_len::validate")]
+    #[validate(custom = "reject_control_chars")]
+    text: String,
+    // Client-supplied so a retried create hits the unique index instead of duplicating.
+    #[serde(default)]
+    uuid: Option<Uuid>,
+}
+
+impl CreateTodo {
+    pub fn new(text: String) -> Self {
+        Self { text, uuid: None }
+    }
+}
+
+// Full replacement for `PUT /todos/by-uuid/:uuid`; the uuid comes from the path.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct UpsertTodo {
+    #[validate(length(min = 1, message = "Can not be empty."))]
+    #[validate(custom = "text_len::validate")]
+    #[validate(custom = "reject_control_chars")]
+    text: String,
+    #[serde(default, deserialize_with = "crate::util::flexible_bool::deserialize")]
+    completed: bool,
+}
+
+// The moved todo plus every neighbour that shifted a slot to make room for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reordered {
+    pub todo: Todo,
+    pub shifted: Vec<Todo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Upserted {
+    Created(Todo),
+    Updated(Todo),
+}
+
+impl Upserted {
+    // Folds the rows a renumbering moved into an import's result: imported rows take their final
+    // position, and rows that were only shifted out of the way are reported as updated.
+    pub fn with_renumbered(mut imported: Vec<Upserted>, renumbered: Vec<Todo>) -> Vec<Upserted> {
+        let mut renumbered: HashMap<i32, Todo> = renumbered.into_iter().map(|todo| (todo.id, todo)).collect();
+        for upserted in &mut imported {
+            let todo = match upserted {
+                Upserted::Created(todo) | Upserted::Updated(todo) => todo,
+            };
+            if let Some(moved) = renumbered.remove(&todo.id) {
+                *todo = moved;
+            }
+        }
+        let mut shifted: Vec<Todo> = renumbered.into_values().collect();
+        shifted.sort_by_key(|todo| todo.id);
+        imported.extend(shifted.into_iter().map(Upserted::Updated));
+        imported
+    }
+}
+
+// `Existing` is the open todo that already carried the text; nothing was written for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CreatedIfAbsent {
+    Created(Todo),
+    Existing(Todo),
+}
+
+// Names the `n`th copy of `text`, trimming the source so the suffix always fits the length limit.
+pub fn copy_text(text: &str, n: usize) -> String {
+    let suffix = match n {
+        1 => " (copy)".to_string(),
+        n => format!(" (copy {})", n),
+    };
+    let keep = text_len::max_text_len().saturating_sub(suffix.chars().count());
+    text.chars().take(keep).chain(suffix.chars()).collect()
+}
+
+// Text rewritten in bulk is held to the same rules as a single update, and named by todo id so
+// the caller can tell which row would have been left invalid.
+pub fn validate_text(todo: &Todo) -> Result<(), RepositoryError> {
+    UpdateTodo::text(todo.text.clone())
+        .validate()
+        .map_err(|e| RepositoryError::BadRequest(format!("todo {}: {}", todo.id, e)))
+}
+
+// Control characters are rejected rather than stripped so the stored text is always what the
+// client sent; tab is the one exception since pasted text routinely carries it.
+pub fn reject_control_chars(text: &str) -> Result<(), ValidationError> {
+    if text.chars().any(|c| c.is_ascii_control() && c != '\t') {
+        let mut error = ValidationError::new("control_characters");
+        error.message = Some("Can not contain control characters.".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateTodo {
+    #[validate(length(min = 1, message = "Can not be empty."))]
+    #[validate(custom = "text_len::validate")]
+    #[validate(custom = "reject_control_chars")]
+    text: Option<String>,
+    #[serde(default, deserialize_with = "crate::util::flexible_bool::deserialize_option")]
+    completed: Option<bool>,
+}
+
+impl UpdateTodo {
+    pub fn text(text: String) -> Self {
+        UpdateTodo {
+            text: Some(text),
+            completed: None,
+        }
+    }
+
+    // Folds RFC 6902 operations into an update; later operations win, as if applied in turn.
+    pub fn from_patch(operations: Vec<PatchOperation>) -> Result<Self, String> {
+        let mut payload = UpdateTodo {
+            text: None,
+            completed: None,
+        };
+        for operation in operations {
+            match (operation.op.as_str(), operation.path.as_str(), operation.value) {
+                ("replace", "/text", Value::String(text)) => payload.text = Some(text),
+                // same spellings a plain `UpdateTodo` body accepts for `completed`
+                ("replace", "/completed", value) => match flexible_bool::deserialize(value) {
+                    Ok(completed) => payload.completed = Some(completed),
+                    Err(_) => return Err(format!("Invalid value for {}", operation.path)),
+                },
+                ("replace", "/text", _) => return Err(format!("Invalid value for {}", operation.path)),
+                (op, path, _) => return Err(format!("Unsupported operation: {} {}", op, path)),
+            }
+        }
+        Ok(payload)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PatchOperation {
+    pub op: String,
+    pub path: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+pub struct ExportBundle {
+    pub schema_version: u32,
+    pub todos: Vec<Todo>,
+}
+
+impl ExportBundle {
+    // Bump whenever the shape of `Todo` changes, and teach `import_todo` to migrate older bundles.
+    pub const SCHEMA_VERSION: u32 = 2;
+
+    pub fn new(todos: Vec<Todo>) -> Self {
+        ExportBundle {
+            schema_version: Self::SCHEMA_VERSION,
+            todos,
+        }
+    }
+
+    // v1 predates `updated_at`; its todos import as touched now so auto-archive doesn't sweep
+    // them on the next tick.
+    pub fn upgrade(mut self) -> Option<Self> {
+        match self.schema_version {
+            1 => {
+                let now = Utc::now();
+                for todo in &mut self.todos {
+                    todo.updated_at = now;
+                }
+                self.schema_version = Self::SCHEMA_VERSION;
+                Some(self)
+            }
+            Self::SCHEMA_VERSION => Some(self),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, FromRow)]
+pub struct AuditLog {
+    pub id: i32,
+    pub todo_id: i32,
+    pub action: String,
+    pub payload_json: Value,
+    pub at: DateTime<Utc>,
+}
+
+impl AuditLog {
+    pub const CREATE: &'static str = "create";
+    pub const UPDATE: &'static str = "update";
+    pub const DELETE: &'static str = "delete";
+}
+
+// Partitioned in Rust from a single `all()` so both buckets come from the same snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TodoBoard {
+    pub open: Vec<Todo>,
+    pub completed: Vec<Todo>,
+}
+
+impl TodoBoard {
+    pub fn new(todos: Vec<Todo>) -> Self {
+        let (completed, open) = todos.into_iter().partition(|todo| todo.completed);
+        TodoBoard { open, completed }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WordCount {
+    pub word: String,
+    pub count: u64,
+}
+
+// Words are runs of letters, digits and apostrophes, lowercased. `total_words` counts every one of
+// them; `top_words` leaves out stop words and breaks count ties alphabetically.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WordStats {
+    pub total_words: u64,
+    pub top_words: Vec<WordCount>,
+}
+
+impl WordStats {
+    pub const TOP_N: usize = 10;
+    const STOP_WORDS: &'static [&'static str] = &[
+        "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "of", "on",
+        "or", "that", "the", "this", "to", "with",
+    ];
+
+    pub fn new<'a>(texts: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut total_words = 0;
+        let mut counts: HashMap<String, u64> = HashMap::new();
+        for text in texts {
+            let words = text
+                .split(|c: char| !c.is_alphanumeric() && c != '\'')
+                .map(|word| word.trim_matches('\''))
+                .filter(|word| !word.is_empty());
+            for word in words {
+                total_words += 1;
+                let word = word.to_lowercase();
+                if !Self::STOP_WORDS.contains(&word.as_str()) {
+                    *counts.entry(word).or_default() += 1;
+                }
+            }
+        }
+
+        let mut top_words: Vec<WordCount> = counts
+            .into_iter()
+            .map(|(word, count)| WordCount { word, count })
+            .collect();
+        top_words.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+        top_words.truncate(Self::TOP_N);
+        WordStats { total_words, top_words }
+    }
+}
+
+// One page, the total it was cut from and the revision it reflects, all read under
+// `REPEATABLE READ` so a write landing mid-request can't make them disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoSnapshot {
+    pub count: i64,
+    pub todos: Vec<Todo>,
+    pub revision: i64,
+}
+
+// `percent` is rounded to one decimal, and an empty list counts as 0% rather than dividing by zero.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TodoProgress {
+    pub completed: i64,
+    pub total: i64,
+    pub percent: f64,
+}
+
+impl TodoProgress {
+    pub fn new(completed: i64, total: i64) -> Self {
+        let percent = if total == 0 {
+            0.0
+        } else {
+            (completed as f64 * 1000.0 / total as f64).round() / 10.0
+        };
+        TodoProgress { completed, total, percent }
+    }
+}
+
+// Direction of the position order used by list endpoints.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(order: &str) -> Result<Self, Self::Err> {
+        match order {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(format!("unknown sort order: {}", order)),
+        }
+    }
+}
+
+// Each `None` predicate matches every todo, so the default filter counts the whole table.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Validate)]
+pub struct TodoFilter {
+    pub completed: Option<bool>,
+}
+
+// What changed after `since`: live todos to upsert locally and ids of hard-deleted ones to drop.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TodoSync {
+    pub todos: Vec<Todo>,
+    pub deleted: Vec<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct ReorderTodo {
+    #[validate(range(min = 1, message = "Position starts at 1."))]
+    pub position: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct SwapTodos {
+    pub a: i32,
+    pub b: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct ReplaceText {
+    #[validate(length(min = 1, message = "Can not be empty."))]
+    #[validate(custom = "reject_control_chars")]
+    pub from: String,
+    #[validate(custom = "text_len::validate")]
+    #[validate(custom = "reject_control_chars")]
+    pub to: String,
+}
+
+#[async_trait]
+impl TodoRepository for TodoRepositoryForDb {
+    async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::create_with(transaction, payload))
+        })
+        .await
+    }
+
+    async fn create_if_absent(&self, payload: CreateTodo) -> anyhow::Result<CreatedIfAbsent> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::create_if_absent_with(transaction, payload))
+        })
+        .await
+    }
+
+    async fn find(&self, id: i32) -> anyhow::Result<Todo> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/findTodo.sql",
+                id
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => RepositoryError::NotFound(id),
+                _ => map_db_error(e),
+            })?;
+
+        Ok(todo)
+    }
+
+    async fn exists(&self, id: i32) -> anyhow::Result<bool> {
+        let exists = sqlx::query_file_scalar!(
+                "sql/existsTodo.sql",
+                id
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(map_db_error)?;
+
+        Ok(exists)
+    }
+
+    async fn count_where(&self, filter: TodoFilter) -> anyhow::Result<i64> {
+        let count = sqlx::query_file_scalar!(
+                "sql/countTodo.sql",
+                filter.completed
+            )
+            .fetch_one(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(count)
+    }
+
+    // Both counts in one statement, so a write between them can't push `completed` past `total`.
+    async fn progress(&self) -> anyhow::Result<TodoProgress> {
+        let counts = sqlx::query_file!("sql/progressTodo.sql")
+            .fetch_one(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(TodoProgress::new(counts.completed, counts.total))
+    }
+
+    async fn find_by_uuid(&self, uuid: Uuid) -> anyhow::Result<Option<Todo>> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/findTodoByUuid.sql",
+                uuid
+            )
+            .fetch_optional(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todo)
+    }
+
+    async fn find_many_by_uuid(&self, uuids: &[Uuid]) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/findTodosByUuid.sql",
+                uuids
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    async fn all(&self) -> anyhow::Result<Vec<Todo>> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/allTodo.sql"
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+    
+        Ok(todo)
+    }
+
+    async fn snapshot(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<TodoSnapshot> {
+        self.with_isolation(IsolationLevel::RepeatableRead, move |transaction| {
+            Box::pin(async move {
+                let count = sqlx::query_file_scalar!("sql/countListedTodo.sql")
+                    .fetch_one(&mut *transaction)
+                    .await.map_err(map_db_error)?;
+                let todos = sqlx::query_file_as!(
+                        Todo,
+                        "sql/pageTodo.sql",
+                        limit,
+                        offset,
+                        order == SortOrder::Desc
+                    )
+                    .fetch_all(&mut *transaction)
+                    .await.map_err(map_db_error)?;
+                let revision = sqlx::query_file_scalar!("sql/todoRevision.sql")
+                    .fetch_one(&mut *transaction)
+                    .await.map_err(map_db_error)?;
+
+                Ok(TodoSnapshot { count, todos, revision })
+            })
+        })
+        .await
+    }
+
+    async fn page(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<Vec<Todo>> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/pageTodo.sql",
+                limit,
+                offset,
+                order == SortOrder::Desc
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todo)
+    }
+
+    // Ids, not positions: gaps left by deleted todos just make the window sparser.
+    async fn window(&self, around_id: i32, radius: i64) -> anyhow::Result<Vec<Todo>> {
+        let (lowest, highest) = window_bounds(around_id, radius);
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/windowTodo.sql",
+                lowest,
+                highest
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    async fn latest(&self) -> anyhow::Result<Option<Todo>> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/latestTodo.sql"
+            )
+            .fetch_optional(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todo)
+    }
+
+    // Only one of them can be open; the rest are completed todos with the same text.
+    async fn find_by_text(&self, text: &str) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/findTodosByText.sql",
+                text
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    // There is no created_at column; ids come from a sequence, so the lowest id was created first.
+    async fn oldest_open(&self) -> anyhow::Result<Option<Todo>> {
+        let todo = sqlx::query_file_as!(
+                Todo,
+                "sql/oldestOpenTodo.sql"
+            )
+            .fetch_optional(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todo)
+    }
+
+    // Todos have no priority column; list position is the order users already curate.
+    async fn top_open(&self, n: i64) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/topOpenTodo.sql",
+                n
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    // `ORDER BY RANDOM()` sorts the whole table; fine for dev data, not for large stores.
+    async fn sample(&self, n: i64) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/sampleTodo.sql",
+                n
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    // There is no completed_at column; a completed todo's last update is taken as its completion,
+    // so editing the text of a done todo moves it into a later report.
+    async fn completed_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/completedBetweenTodo.sql",
+                from,
+                to
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    async fn modified_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        let todos = sqlx::query_file_as!(
+                Todo,
+                "sql/modifiedSinceTodo.sql",
+                since
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(todos)
+    }
+
+    // Deletes are hard, so tombstones come from the audit log rather than the todos table.
+    async fn deleted_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<i32>> {
+        let ids = sqlx::query_file_scalar!(
+                "sql/deletedSinceTodo.sql",
+                since
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(ids)
+    }
+
+    // Bumped by a statement trigger on every write to `todos`, so it covers all mutation paths.
+    async fn revision(&self) -> anyhow::Result<i64> {
+        let revision = sqlx::query_file_scalar!("sql/todoRevision.sql")
+            .fetch_one(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(revision)
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, anyhow::Result<Todo>> {
+        let pool = self.pool.clone();
+        Box::pin(async_stream::try_stream! {
+            let mut rows = sqlx::query_file_as!(
+                    Todo,
+                    "sql/allTodo.sql"
+                )
+                .fetch(&pool);
+
+            while let Some(todo) = rows.try_next().await.map_err(map_db_error)? {
+                yield todo;
+            }
+        })
+    }
+
+    async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::update_with(transaction, id, payload))
+        })
+        .await
+    }
+
+    async fn duplicate(&self, id: i32) -> anyhow::Result<Todo> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::duplicate_with(transaction, id))
+        })
+        .await
+    }
+
+    async fn upsert(&self, uuid: Uuid, payload: UpsertTodo) -> anyhow::Result<Upserted> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::upsert_with(transaction, uuid, payload))
+        })
+        .await
+    }
+
+    async fn reorder(&self, id: i32, position: i32) -> anyhow::Result<Reordered> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::reorder_with(transaction, id, position))
+        })
+        .await
+    }
+
+    async fn swap(&self, id_a: i32, id_b: i32) -> anyhow::Result<(Todo, Todo)> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::swap_with(transaction, id_a, id_b))
+        })
+        .await
+    }
+
+    async fn set_all_completed(&self, completed: bool) -> anyhow::Result<Vec<Todo>> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::set_all_completed_with(transaction, completed))
+        })
+        .await
+    }
+
+    async fn replace_text(&self, from: &str, to: &str) -> anyhow::Result<Vec<Todo>> {
+        let (from, to) = (from.to_string(), to.to_string());
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::replace_text_with(transaction, from, to))
+        })
+        .await
+    }
+
+    async fn archive_completed(&self, updated_before: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::archive_completed_with(transaction, updated_before))
+        })
+        .await
+    }
+
+    async fn import(&self, todos: Vec<Todo>) -> anyhow::Result<Vec<Upserted>> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::import_with(transaction, todos))
+        })
+        .await
+    }
+
+    async fn delete(&self, id: i32) -> anyhow::Result<()> {
+        self.with_transaction(move |transaction| {
+            Box::pin(Self::delete_with(transaction, id))
+        })
+        .await
+    }
+
+    async fn history(&self, id: i32) -> anyhow::Result<Vec<AuditLog>> {
+        let entries = sqlx::query_file_as!(
+                AuditLog,
+                "sql/historyTodo.sql",
+                id
+            )
+            .fetch_all(&self.pool)
+            .await.map_err(map_db_error)?;
+
+        Ok(entries)
+    }
+
+    async fn pending_migrations(&self) -> anyhow::Result<Vec<i64>> {
+        self.pending_in(&MIGRATOR).await
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "database-test")]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use sqlx::migrate::{Migration, MigrationType};
+    use dotenv::dotenv;
+    use sqlx::PgPool;
+    use std::env;
+
+    async fn initialization_test_pool() -> PgPool {
+        dotenv().ok();
+        let database_url = env::var("DATABASE_URL")
+            .expect("DATABASE URL MUST BE SET.");
+
+        sqlx::postgres::PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&database_url)
+            .await
+            .unwrap_or_else(|_| {
+                panic!("Failed create connection pool.")
+            })
+    }
+
+    #[tokio::test]
+    async fn crud_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let todo_text = "[crud_scenario] text";
+
+        // create
+        let created = repositry
+            .create(CreateTodo::new(todo_text.to_string()))
+            .await
+            .expect("[create] returned Err");
+
+        assert_eq!(created.text, todo_text);
+        assert!(!created.completed);
+
+        // find
+        let todo = repositry
+            .find(created.id)
+            .await
+            .expect("[find] returned Err");
+        assert_eq!(created, todo);
+
+        // exists
+        assert!(repositry.exists(created.id).await.expect("[exists] returned Err"));
+        let todos = repositry.all().await.expect("[all] returned Err");
+        let todo = todos.iter().find(|todo| todo.id == created.id).unwrap();
+        assert_eq!(created, *todo);
+
+        // snapshot
+        let snapshot = repositry.snapshot(i64::MAX, 0, SortOrder::Asc).await.expect("[snapshot] returned Err");
+        assert_eq!(snapshot.count, snapshot.todos.len() as i64);
+        assert!(snapshot.todos.iter().all(|todo| !todo.archived));
+        let snapshot = repositry.snapshot(1, 0, SortOrder::Desc).await.expect("[snapshot] returned Err");
+        assert_eq!(1, snapshot.todos.len());
+        assert!(snapshot.count >= 1);
+
+        // progress
+        let progress = repositry.progress().await.expect("[progress] returned Err");
+        assert!(1 <= progress.total && progress.completed <= progress.total, "{:?}", progress);
+
+        // page
+        let page = repositry.page(1, 0, SortOrder::Asc).await.expect("[page] returned Err");
+        assert_eq!(1, page.len());
+        let first = repositry.page(1, 0, SortOrder::Desc).await.expect("[page] returned Err");
+        assert!(first[0].position >= page[0].position);
+
+        // stream_all
+        let streamed: Vec<Todo> = repositry
+            .stream_all()
+            .try_collect()
+            .await
+            .expect("[stream_all] returned Err");
+        assert!(streamed.contains(&created));
+
+        // update
+        let updated_text = "[crud_scenario] update text";
+        let todo = repositry
+            .update(
+                todo.id,
+                UpdateTodo { 
+                    text: Some(updated_text.to_string()),
+                    completed: Some(true) 
+                }
+            )
+            .await
+            .expect("[update] returned Err");
+        
+        assert_eq!(created.id, todo.id);
+        assert_eq!(todo.text, updated_text);
+
+        // partial update leaves omitted fields unchanged
+        let todo = repositry
+            .update(todo.id, UpdateTodo { text: None, completed: Some(false) })
+            .await
+            .expect("[update] returned Err");
+        assert_eq!(updated_text, todo.text);
+        assert!(!todo.completed);
+        let todo = repositry
+            .update(todo.id, UpdateTodo { text: None, completed: None })
+            .await
+            .expect("[update] returned Err");
+        assert_eq!(updated_text, todo.text);
+        assert!(!todo.completed);
+        let res = repositry.update(-1, UpdateTodo::text(updated_text.to_string())).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<RepositoryError>(),
+            Some(RepositoryError::NotFound(-1))
+        ));
+
+        // revision
+        let revision = repositry.revision().await.expect("[revision] returned Err");
+        repositry
+            .update(todo.id, UpdateTodo::text(updated_text.to_string()))
+            .await
+            .expect("[update] returned Err");
+        assert!(repositry.revision().await.expect("[revision] returned Err") > revision);
+
+        // latest
+        let latest = repositry
+            .latest()
+            .await
+            .expect("[latest] returned Err")
+            .expect("[latest] returned None");
+        assert!(latest.updated_at >= todo.updated_at);
+
+        // sample
+        let sample = repositry.sample(2).await.expect("[sample] returned Err");
+        assert!(!sample.is_empty() && sample.len() <= 2, "sample of {}", sample.len());
+
+        // window
+        let window = repositry.window(todo.id, 1).await.expect("[window] returned Err");
+        assert!(window.iter().any(|t| t.id == todo.id));
+        assert!(window.iter().all(|t| (todo.id - 1..=todo.id + 1).contains(&t.id)), "{:?}", window);
+        assert!(window.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+        // duplicate: copies are named apart so they never clash with the open source
+        let copies = [
+            repositry.duplicate(todo.id).await.expect("[duplicate] returned Err"),
+            repositry.duplicate(todo.id).await.expect("[duplicate] returned Err"),
+        ];
+        assert_eq!(format!("{} (copy)", todo.text), copies[0].text);
+        assert_eq!(format!("{} (copy 2)", todo.text), copies[1].text);
+        for copy in copies {
+            repositry.delete(copy.id).await.expect("[delete copy] returned Err");
+        }
+        let todo = repositry
+            .update(todo.id, UpdateTodo { text: None, completed: Some(true) })
+            .await
+            .expect("[update] returned Err");
+        let copy = repositry
+            .duplicate(todo.id)
+            .await
+            .expect("[duplicate] returned Err");
+        assert_ne!(todo.id, copy.id);
+        assert_ne!(todo.uuid, copy.uuid);
+        assert_eq!(format!("{} (copy)", todo.text), copy.text);
+        assert!(!copy.completed);
+        repositry
+            .delete(copy.id)
+            .await
+            .expect("[delete copy] returned Err");
+
+        // delete
+        repositry
+            .delete(todo.id)
+            .await
+            .expect("[delete] returned Err");
+
+        let res = repositry.find(created.id).await;
+        assert!(res.is_err());
+        assert!(!repositry.exists(created.id).await.expect("[exists] returned Err"));
+
+        let todo_rows = sqlx::query_file_as!(
+            Todo,
+            "sql/findTodo.sql",
+            todo.id
+        )
+        .fetch_all(&pool)
+        .await
+        .expect("[delete] todo_labes featch error");
+        
+        assert!(todo_rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn duplicate_uuid_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let uuid = Uuid::new_v4();
+
+        let created = repositry
+            .create(CreateTodo::with_uuid("[duplicate_uuid_scenario] text".to_string(), uuid))
+            .await
+            .expect("[create] returned Err");
+        assert_eq!(uuid, created.uuid);
+
+        let res = repositry
+            .create(CreateTodo::with_uuid("[duplicate_uuid_scenario] retry".to_string(), uuid))
+            .await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<RepositoryError>(),
+            Some(RepositoryError::Duplicate(duplicate)) if *duplicate == uuid
+        ));
+
+        let todo = repositry
+            .find_by_uuid(uuid)
+            .await
+            .expect("[find_by_uuid] returned Err");
+        assert_eq!(Some(created.clone()), todo);
+
+        let unknown = Uuid::new_v4();
+        let todos = repositry
+            .find_many_by_uuid(&[unknown, uuid, unknown])
+            .await
+            .expect("[find_many_by_uuid] returned Err");
+        assert_eq!(vec![created.clone()], todos);
+
+        repositry
+            .delete(created.id)
+            .await
+            .expect("[delete] returned Err");
+    }
+
+    #[tokio::test]
+    async fn open_text_unique_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let text = "[open_text_unique_scenario] text";
+        let first = repositry
+            .create(CreateTodo::new(text.to_string()))
+            .await
+            .expect("[create] returned Err");
+
+        let res = repositry.create(CreateTodo::new(text.to_string())).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<RepositoryError>(),
+            Some(RepositoryError::Conflict(_))
+        ));
+
+        repositry
+            .update(first.id, UpdateTodo { text: None, completed: Some(true) })
+            .await
+            .expect("[update] returned Err");
+        let second = repositry
+            .create(CreateTodo::new(text.to_string()))
+            .await
+            .expect("[create] returned Err");
+
+        let found = repositry.find_by_text(text).await.expect("[find_by_text] returned Err");
+        assert_eq!(vec![first.id, second.id], found.iter().map(|todo| todo.id).collect::<Vec<_>>());
+        for near in [text.to_uppercase(), format!("{} ", text)] {
+            let found = repositry.find_by_text(&near).await.expect("[find_by_text] returned Err");
+            assert!(found.is_empty(), "{:?} matched {:?}", near, found);
+        }
+
+        for id in [first.id, second.id] {
+            repositry.delete(id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn create_if_absent_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let text = "[create_if_absent_scenario] text";
+        let created = match repositry
+            .create_if_absent(CreateTodo::new(text.to_string()))
+            .await
+            .expect("[create_if_absent] returned Err")
+        {
+            CreatedIfAbsent::Created(todo) => todo,
+            existing => panic!("expected Created, got {:?}", existing),
+        };
+
+        let again = repositry
+            .create_if_absent(CreateTodo::new(text.to_string()))
+            .await
+            .expect("[create_if_absent] returned Err");
+        assert_eq!(CreatedIfAbsent::Existing(created.clone()), again);
+        let history = repositry.history(created.id).await.expect("[history] returned Err");
+        assert_eq!(1, history.len());
+
+        repositry.delete(created.id).await.expect("[delete] returned Err");
+    }
+
+    #[tokio::test]
+    async fn replace_text_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let mut ids = Vec::new();
+        for text in ["[replace_text_scenario] 100%_apple pie", "[replace_text_scenario] 100%_apple tart", "[replace_text_scenario] 100Xapple"] {
+            let todo = repositry
+                .create(CreateTodo::new(text.to_string()))
+                .await
+                .expect("[create] returned Err");
+            ids.push(todo.id);
+        }
+
+        let replaced = repositry
+            .replace_text("] 100%_apple", "] 100%_pear")
+            .await
+            .expect("[replace_text] returned Err");
+        assert_eq!(2, replaced.len());
+        for todo in &replaced {
+            let history = repositry.history(todo.id).await.expect("[history] returned Err");
+            assert_eq!(AuditLog::UPDATE, history.last().unwrap().action);
+        }
+
+        let res = repositry.replace_text("[replace_text_scenario] 100Xapple", "").await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<RepositoryError>(),
+            Some(RepositoryError::BadRequest(_))
+        ));
+        let mut texts = Vec::new();
+        for id in &ids {
+            texts.push(repositry.find(*id).await.expect("[find] returned Err").text);
+        }
+        assert_eq!(
+            vec![
+                "[replace_text_scenario] 100%_pear pie",
+                "[replace_text_scenario] 100%_pear tart",
+                "[replace_text_scenario] 100Xapple",
+            ],
+            texts
+        );
+
+        for id in ids {
+            repositry.delete(id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn pending_migration_is_reported() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let unreleased = Migration::new(
+            99_991_231_000_000,
+            "unreleased".into(),
+            MigrationType::Simple,
+            "SELECT 1".into(),
+        );
+        let migrator = Migrator {
+            migrations: MIGRATOR.iter().cloned().chain([unreleased]).collect(),
+            ignore_missing: false,
+        };
+
+        let pending = repositry.pending_in(&migrator).await.expect("[pending_in] returned Err");
+        assert_eq!(Some(&99_991_231_000_000), pending.last());
+    }
+
+    #[tokio::test]
+    async fn upsert_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let uuid = Uuid::new_v4();
+
+        let created = match repositry
+            .upsert(uuid, UpsertTodo::new("[upsert_scenario] text".to_string(), false))
+            .await
+            .expect("[upsert] returned Err")
+        {
+            Upserted::Created(todo) => todo,
+            other => panic!("[upsert] expected insert, got {:?}", other),
+        };
+        assert_eq!(uuid, created.uuid);
+
+        let updated = match repositry
+            .upsert(uuid, UpsertTodo::new("[upsert_scenario] edited".to_string(), true))
+            .await
+            .expect("[upsert] returned Err")
+        {
+            Upserted::Updated(todo) => todo,
+            other => panic!("[upsert] expected update, got {:?}", other),
+        };
+        assert_eq!(created.id, updated.id);
+        assert_eq!(created.position, updated.position);
+        assert_eq!("[upsert_scenario] edited", updated.text);
+        assert!(updated.completed);
+        assert_eq!(Some(updated.clone()), repositry.find_by_uuid(uuid).await.expect("[find_by_uuid] returned Err"));
+
+        repositry
+            .delete(created.id)
+            .await
+            .expect("[delete] returned Err");
+    }
+
+    #[tokio::test]
+    async fn reorder_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let mut created = Vec::new();
+        for text in ["a", "b", "c"] {
+            let todo = repositry
+                .create(CreateTodo::new(format!("[reorder_scenario] {}", text)))
+                .await
+                .expect("[create] returned Err");
+            created.push(todo);
+        }
+        let first = repositry.find(created[0].id).await.unwrap();
+
+        // move the last one in front of the first one
+        let moved = repositry
+            .reorder(created[2].id, first.position)
+            .await
+            .expect("[reorder] returned Err");
+        assert_eq!(first.position, moved.todo.position);
+        let shifted: Vec<i32> = moved
+            .shifted
+            .iter()
+            .map(|todo| todo.id)
+            .filter(|id| created.iter().any(|c| c.id == *id))
+            .collect();
+        assert_eq!(vec![created[0].id, created[1].id], shifted);
+        for todo in &created {
+            let history = repositry.history(todo.id).await.expect("[history] returned Err");
+            assert_eq!(AuditLog::UPDATE, history.last().unwrap().action);
+        }
+
+        let ids: Vec<i32> = repositry
+            .all()
+            .await
+            .expect("[all] returned Err")
+            .into_iter()
+            .filter(|todo| created.iter().any(|c| c.id == todo.id))
+            .map(|todo| todo.id)
+            .collect();
+        assert_eq!(vec![created[2].id, created[0].id, created[1].id], ids);
+
+        repositry.bump(created[1].id, SortOrder::Asc).await.expect("[bump] returned Err");
+        let ids: Vec<i32> = repositry
+            .all()
+            .await
+            .expect("[all] returned Err")
+            .into_iter()
+            .filter(|todo| created.iter().any(|c| c.id == todo.id))
+            .map(|todo| todo.id)
+            .collect();
+        assert_eq!(vec![created[1].id, created[2].id, created[0].id], ids);
+
+        for todo in created {
+            repositry.delete(todo.id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn swap_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let mut created = Vec::new();
+        for text in ["a", "b"] {
+            let todo = repositry
+                .create(CreateTodo::new(format!("[swap_scenario] {}", text)))
+                .await
+                .expect("[create] returned Err");
+            created.push(todo);
+        }
+        let (a, b) = (&created[0], &created[1]);
+
+        let (swapped_a, swapped_b) = repositry.swap(a.id, b.id).await.expect("[swap] returned Err");
+        assert_eq!((b.position, a.position), (swapped_a.position, swapped_b.position));
+        assert_eq!(b.position, repositry.find(a.id).await.unwrap().position);
+        assert_eq!(a.position, repositry.find(b.id).await.unwrap().position);
+
+        let res = repositry.swap(a.id, -1).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<RepositoryError>(),
+            Some(RepositoryError::NotFound(-1))
+        ));
+        assert_eq!(b.position, repositry.find(a.id).await.unwrap().position);
+
+        for todo in created {
+            repositry.delete(todo.id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn page_breaks_position_ties_by_id() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let mut ids = vec![];
+        for i in 0..7 {
+            let todo = repositry
+                .create(CreateTodo::new(format!("[page_breaks_position_ties_by_id] {}", i)))
+                .await
+                .expect("[create] returned Err");
+            ids.push(todo.id);
+        }
+        // below any position the other tests hand out, so these rows stay the first pages while they run
+        sqlx::query("UPDATE todos SET position = -1000 WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&pool)
+            .await
+            .expect("[tie positions] returned Err");
+
+        let mut paged = vec![];
+        for offset in [0, 3, 6] {
+            let page = repositry.page(3, offset, SortOrder::Asc).await.expect("[page] returned Err");
+            paged.extend(page.into_iter().map(|todo| todo.id).filter(|id| ids.contains(id)));
+        }
+        assert_eq!(ids, paged);
+
+        for id in ids {
+            repositry.delete(id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn import_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let todo = Todo {
+            id: 1_000_000 + rand_id(),
+            text: "[import_scenario] text".to_string(),
+            completed: true,
+            uuid: Uuid::new_v4(),
+            position: i32::MAX,
+            updated_at: Utc.timestamp_opt(1_600_000_000, 0).unwrap(),
+            archived: false,
+        };
+
+        let imported = repositry
+            .import(vec![todo.clone()])
+            .await
+            .expect("[import] returned Err");
+        assert!(matches!(&imported[0], Upserted::Created(created) if created.id == todo.id));
+
+        // the bundle's position only orders the import; it lands on the next free slot
+        let found = repositry.find(todo.id).await.expect("[find] returned Err");
+        assert_eq!(Todo { position: found.position, ..todo.clone() }, found);
+        assert!(found.position < i32::MAX);
+        let history = repositry.history(todo.id).await.expect("[history] returned Err");
+        assert_eq!(vec![AuditLog::CREATE], history.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>());
+
+        let invalid = Todo { text: String::new(), ..todo.clone() };
+        let res = repositry.import(vec![invalid]).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<RepositoryError>(),
+            Some(RepositoryError::BadRequest(_))
+        ));
+
+        // newly created todos keep getting fresh ids past the imported one
+        let created = repositry
+            .create(CreateTodo::new("[import_scenario] created".to_string()))
+            .await
+            .expect("[create] returned Err");
+        assert!(created.id > todo.id);
+
+        repositry.delete(todo.id).await.expect("[delete] returned Err");
+        repositry.delete(created.id).await.expect("[delete] returned Err");
+    }
+
+    fn rand_id() -> i32 {
+        (Uuid::new_v4().as_u128() % 1_000_000) as i32
+    }
+
+    #[tokio::test]
+    async fn archive_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let mut ids = vec![];
+        for (text, completed, days_ago) in [
+            ("[archive_scenario] old completed", true, 40),
+            ("[archive_scenario] recent completed", true, 1),
+            ("[archive_scenario] old open", false, 40),
+        ] {
+            let todo = repositry
+                .create(CreateTodo::new(text.to_string()))
+                .await
+                .expect("[create] returned Err");
+            sqlx::query("UPDATE todos SET completed = $1, updated_at = now() - make_interval(days => $2) WHERE id = $3")
+                .bind(completed)
+                .bind(days_ago)
+                .bind(todo.id)
+                .execute(&pool)
+                .await
+                .expect("[backdate] returned Err");
+            ids.push(todo.id);
+        }
+
+        let swept = repositry
+            .archive_completed(Utc::now() - chrono::Duration::days(30))
+            .await
+            .expect("[archive_completed] returned Err");
+        assert!(swept.iter().any(|todo| todo.id == ids[0] && todo.archived));
+
+        let mut archived = vec![];
+        for id in &ids {
+            archived.push(repositry.find(*id).await.expect("[find] returned Err").archived);
+        }
+        assert_eq!(vec![true, false, false], archived);
+
+        // reopening an archived todo lists it again
+        let reopened = repositry
+            .update(ids[0], UpdateTodo { text: None, completed: Some(false) })
+            .await
+            .expect("[update] returned Err");
+        assert!(!reopened.archived);
+        let listed = repositry.page(i64::MAX, 0, SortOrder::Asc).await.expect("[page] returned Err");
+        assert!(listed.iter().any(|todo| todo.id == ids[0]));
+
+        for id in &ids {
+            repositry.delete(*id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn completed_between_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let mut ids = vec![];
+        for (text, completed, days_ago) in [
+            ("[completed_between_scenario] in window", true, 5),
+            ("[completed_between_scenario] too old", true, 20),
+            ("[completed_between_scenario] too recent", true, 1),
+            ("[completed_between_scenario] open", false, 5),
+        ] {
+            let todo = repositry
+                .create(CreateTodo::new(text.to_string()))
+                .await
+                .expect("[create] returned Err");
+            sqlx::query("UPDATE todos SET completed = $1, updated_at = now() - make_interval(days => $2) WHERE id = $3")
+                .bind(completed)
+                .bind(days_ago)
+                .bind(todo.id)
+                .execute(&pool)
+                .await
+                .expect("[backdate] returned Err");
+            ids.push(todo.id);
+        }
+
+        let now = Utc::now();
+        let completed: Vec<i32> = repositry
+            .completed_between(now - chrono::Duration::days(10), now - chrono::Duration::days(2))
+            .await
+            .expect("[completed_between] returned Err")
+            .into_iter()
+            .map(|todo| todo.id)
+            .filter(|id| ids.contains(id))
+            .collect();
+        assert_eq!(vec![ids[0]], completed);
+
+        for id in ids {
+            repositry.delete(id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let untouched = repositry
+            .create(CreateTodo::new("[sync_scenario] untouched".to_string()))
+            .await
+            .expect("[create] returned Err");
+        let updated = repositry
+            .create(CreateTodo::new("[sync_scenario] updated".to_string()))
+            .await
+            .expect("[create] returned Err");
+        let deleted = repositry
+            .create(CreateTodo::new("[sync_scenario] deleted".to_string()))
+            .await
+            .expect("[create] returned Err");
+        let since = repositry.find(deleted.id).await.expect("[find] returned Err").updated_at;
+
+        repositry
+            .update(updated.id, UpdateTodo::text("[sync_scenario] updated!".to_string()))
+            .await
+            .expect("[update] returned Err");
+        repositry.delete(deleted.id).await.expect("[delete] returned Err");
+
+        let ids = [untouched.id, updated.id, deleted.id];
+        let modified: Vec<i32> = repositry
+            .modified_since(since)
+            .await
+            .expect("[modified_since] returned Err")
+            .into_iter()
+            .map(|todo| todo.id)
+            .filter(|id| ids.contains(id))
+            .collect();
+        assert_eq!(vec![updated.id], modified);
+        let tombstones: Vec<i32> = repositry
+            .deleted_since(since)
+            .await
+            .expect("[deleted_since] returned Err")
+            .into_iter()
+            .filter(|id| ids.contains(id))
+            .collect();
+        assert_eq!(vec![deleted.id], tombstones);
+
+        for id in [untouched.id, updated.id] {
+            repositry.delete(id).await.expect("[delete] returned Err");
+        }
+    }
+
+    #[tokio::test]
+    async fn audit_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let created = repositry
+            .create(CreateTodo::new("[audit_scenario] text".to_string()))
+            .await
+            .expect("[create] returned Err");
+        repositry
+            .update(created.id, UpdateTodo::text("[audit_scenario] update".to_string()))
+            .await
+            .expect("[update] returned Err");
+        repositry.delete(created.id).await.expect("[delete] returned Err");
+
+        let history = repositry.history(created.id).await.expect("[history] returned Err");
+        let actions = history.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec![AuditLog::CREATE, AuditLog::UPDATE, AuditLog::DELETE], actions);
+        assert_eq!("[audit_scenario] update", history[1].payload_json["text"]);
+    }
+
+    #[tokio::test]
+    async fn transaction_rollback_scenario() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let todo_text = "[transaction_rollback_scenario] text";
+
+        let res = repositry
+            .with_transaction(|transaction| {
+                Box::pin(async move {
+                    TodoRepositoryForDb::create_with(
+                        &mut *transaction,
+                        CreateTodo::new(todo_text.to_string()),
+                    )
+                    .await?;
+                    TodoRepositoryForDb::update_with(
+                        &mut *transaction,
+                        -1,
+                        UpdateTodo {
+                            text: Some(todo_text.to_string()),
+                            completed: Some(true),
+                        },
+                    )
+                    .await
+                })
+            })
+            .await;
+        assert!(res.is_err());
+
+        let todo_rows = sqlx::query_as::<_, Todo>("SELECT * FROM todos WHERE text = $1")
+            .bind(todo_text)
+            .fetch_all(&pool)
+            .await
+            .expect("[rollback] todo featch error");
+
+        assert!(todo_rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn repeatable_read_ignores_concurrent_insert() {
+        let pool = initialization_test_pool().await;
+
+        let repositry = TodoRepositoryForDb::new(pool.clone());
+        let inserted = repositry
+            .with_isolation(IsolationLevel::RepeatableRead, {
+                let repositry = repositry.clone();
+                move |transaction| {
+                    Box::pin(async move {
+                        let count = "SELECT COUNT(*) FROM todos";
+                        let before: i64 = sqlx::query_scalar(count).fetch_one(&mut *transaction).await?;
+                        // committed on another connection while this snapshot is open
+                        let inserted = repositry
+                            .create(CreateTodo::new("[repeatable_read_scenario] text".to_string()))
+                            .await?;
+                        let after: i64 = sqlx::query_scalar(count).fetch_one(&mut *transaction).await?;
+                        assert_eq!(before, after);
+                        Ok(inserted)
+                    })
+                }
+            })
+            .await
+            .expect("[with_isolation] returned Err");
+
+        repositry.delete(inserted.id).await.expect("[delete] returned Err");
+    }
+}
+
+#[cfg(test)]
+mod error_test {
+    use super::*;
+    use std::{borrow::Cow, error::Error as StdError, fmt};
+
+    #[derive(Debug)]
+    struct SyntheticDbError {
+        code: &'static str,
+    }
+
+    impl fmt::Display for SyntheticDbError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "synthetic error {}", self.code)
+        }
+    }
+
+    impl StdError for SyntheticDbError {}
+
+    impl sqlx::error::DatabaseError for SyntheticDbError {
+        fn message(&self) -> &str {
+            "synthetic error"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.code))
+        }
+
+        fn as_error(&self) -> &(dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn StdError + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn StdError + Send + Sync + 'static> {
+            self
+        }
+    }
+
+    fn synthetic(code: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(SyntheticDbError { code }))
+    }
+
+    #[test]
+    fn map_db_error_by_sqlstate() {
+        assert!(matches!(map_db_error(synthetic("23505")), RepositoryError::Conflict(_)));
+        assert!(matches!(map_db_error(synthetic("23503")), RepositoryError::BadRequest(_)));
+        assert!(matches!(map_db_error(synthetic("40001")), RepositoryError::Unexpected(_)));
+        assert!(matches!(map_db_error(sqlx::Error::PoolClosed), RepositoryError::Unexpected(_)));
+        assert!(matches!(map_db_error(sqlx::Error::PoolTimedOut), RepositoryError::Unavailable(_)));
+    }
+}
+
+// Process-local store used by the unit tests and as the `ALLOW_MEMORY_FALLBACK` backend.
+pub mod memory {
+    use anyhow::Context;
+    use axum::async_trait;
+    use rand::seq::SliceRandom;
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicI64, Ordering},
+            Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        },
+    };
+
+    use super::*;
+
+    type TodoDatas = HashMap<i32, Todo>;
+
+    #[derive(Debug, Clone)]
+    pub struct TodoRepositoryForMemory {
+        store: Arc<RwLock<TodoDatas>>,
+        audit: Arc<RwLock<Vec<AuditLog>>>,
+        revision: Arc<AtomicI64>,
+    }
+
+    impl TodoRepositoryForMemory {
+        pub fn new() -> Self {
+            TodoRepositoryForMemory {
+                store: Arc::default(),
+                audit: Arc::default(),
+                revision: Arc::default(),
+            }
+        }
+
+        fn record(&self, action: &str, todo: &Todo) {
+            let mut audit = self.audit.write().unwrap();
+            let entry = AuditLog {
+                id: audit.len() as i32 + 1,
+                todo_id: todo.id,
+                action: action.to_string(),
+                payload_json: serde_json::to_value(todo).unwrap(),
+                at: Utc::now(),
+            };
+            audit.push(entry);
+        }
+
+        // Mirrors the `todos_open_text_key` partial unique index.
+        fn check_open_text(store: &TodoDatas, id: i32, text: &str, completed: bool) -> Result<(), RepositoryError> {
+            let taken = !completed
+                && store
+                    .values()
+                    .any(|other| other.id != id && !other.completed && other.text == text);
+            if taken {
+                return Err(RepositoryError::Conflict(format!("open todo text already exists: {}", text)));
+            }
+            Ok(())
+        }
+
+        // `todos` arrives in ascending order, as from `sorted()`.
+        fn paged(mut todos: Vec<Todo>, limit: i64, offset: i64, order: SortOrder) -> Vec<Todo> {
+            if order == SortOrder::Desc {
+                todos.reverse();
+            }
+            todos.into_iter().skip(offset as usize).take(limit as usize).collect()
+        }
+
+        // Same order as `ORDER BY POSITION`; the id only breaks ties the `HashMap` would leave random.
+        fn sorted(&self) -> Vec<Todo> {
+            let store = self.read_store_ref();
+            let mut todos = Vec::from_iter(store.values().cloned());
+            todos.sort_by_key(|todo| (todo.position, todo.id));
+            todos
+        }
+
+        // Every write counts as a revision, even one that fails; a spurious bump only costs a refetch.
+        // Bumped under the lock so a reader that sees the new revision waits for the new store.
+        fn write_store_ref(&self) -> RwLockWriteGuard<'_, TodoDatas> {
+            let store = self.store.write().unwrap();
+            self.revision.fetch_add(1, Ordering::SeqCst);
+            store
+        }
+
+        fn read_store_ref(&self) -> RwLockReadGuard<'_, TodoDatas> {
+            self.store.read().unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl TodoRepository for TodoRepositoryForMemory {
+        async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo> {
+            let mut store = self.write_store_ref();
+            let uuid = payload.uuid.unwrap_or_else(Uuid::new_v4);
+            if store.values().any(|todo| todo.uuid == uuid) {
+                return Err(RepositoryError::Duplicate(uuid).into());
+            }
+            Self::check_open_text(&store, 0, &payload.text, false)?;
+            let id = store.keys().max().unwrap_or(&0) + 1;
+            let position = store.values().map(|todo| todo.position).max().unwrap_or(0) + 1;
+            let todo = Todo {
+                uuid,
+                position,
+                updated_at: Utc::now(),
+                ..Todo::new(id, payload.text)
+            };
+            store.insert(id, todo.clone());
+            self.record(AuditLog::CREATE, &todo);
+            Ok(todo)
+        }
+
+        async fn find(&self, id: i32) -> anyhow::Result<Todo> {
+            let store = self.read_store_ref();
+            let todo = store
+                .get(&id)
+                .cloned()
+                .ok_or(RepositoryError::NotFound(id))?;
+            Ok(todo)
+        }
+
+        async fn exists(&self, id: i32) -> anyhow::Result<bool> {
+            let store = self.read_store_ref();
+            Ok(store.contains_key(&id))
+        }
+
+        async fn count_where(&self, filter: TodoFilter) -> anyhow::Result<i64> {
+            let store = self.read_store_ref();
+            Ok(store
+                .values()
+                .filter(|todo| filter.completed.is_none_or(|completed| todo.completed == completed))
+                .count() as i64)
+        }
+
+        async fn progress(&self) -> anyhow::Result<TodoProgress> {
+            let store = self.read_store_ref();
+            let completed = store.values().filter(|todo| todo.completed).count() as i64;
+            Ok(TodoProgress::new(completed, store.len() as i64))
+        }
+
+        async fn find_by_uuid(&self, uuid: Uuid) -> anyhow::Result<Option<Todo>> {
+            let store = self.read_store_ref();
+            Ok(store.values().find(|todo| todo.uuid == uuid).cloned())
+        }
+
+        async fn find_many_by_uuid(&self, uuids: &[Uuid]) -> anyhow::Result<Vec<Todo>> {
+            Ok(self
+                .sorted()
+                .into_iter()
+                .filter(|todo| uuids.contains(&todo.uuid))
+                .collect())
+        }
+
+        async fn all(&self) -> anyhow::Result<Vec<Todo>> {
+            Ok(self.sorted())
+        }
+
+        // Writers bump the revision under the write lock, so one read guard covers all three.
+        async fn snapshot(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<TodoSnapshot> {
+            let store = self.read_store_ref();
+            let revision = self.revision.load(Ordering::SeqCst);
+            let mut todos: Vec<Todo> = store.values().filter(|todo| !todo.archived).cloned().collect();
+            todos.sort_by_key(|todo| (todo.position, todo.id));
+            Ok(TodoSnapshot {
+                count: todos.len() as i64,
+                todos: Self::paged(todos, limit, offset, order),
+                revision,
+            })
+        }
+
+        async fn page(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<Vec<Todo>> {
+            let todos = self.all().await?.into_iter().filter(|todo| !todo.archived).collect();
+            Ok(Self::paged(todos, limit, offset, order))
+        }
+
+        async fn window(&self, around_id: i32, radius: i64) -> anyhow::Result<Vec<Todo>> {
+            let (lowest, highest) = window_bounds(around_id, radius);
+            let store = self.read_store_ref();
+            let mut todos: Vec<Todo> = store
+                .values()
+                .filter(|todo| (lowest..=highest).contains(&todo.id) && !todo.archived)
+                .cloned()
+                .collect();
+            todos.sort_by_key(|todo| todo.id);
+            Ok(todos)
+        }
+
+        async fn latest(&self) -> anyhow::Result<Option<Todo>> {
+            let store = self.read_store_ref();
+            Ok(store
+                .values()
+                .max_by_key(|todo| (todo.updated_at, todo.id))
+                .cloned())
+        }
+
+        async fn find_by_text(&self, text: &str) -> anyhow::Result<Vec<Todo>> {
+            Ok(self
+                .sorted()
+                .into_iter()
+                .filter(|todo| todo.text == text)
+                .collect())
+        }
+
+        async fn oldest_open(&self) -> anyhow::Result<Option<Todo>> {
+            let store = self.read_store_ref();
+            Ok(store
+                .values()
+                .filter(|todo| !todo.completed)
+                .min_by_key(|todo| todo.id)
+                .cloned())
+        }
+
+        async fn top_open(&self, n: i64) -> anyhow::Result<Vec<Todo>> {
+            Ok(self
+                .sorted()
+                .into_iter()
+                .filter(|todo| !todo.completed)
+                .take(n as usize)
+                .collect())
+        }
+
+        async fn sample(&self, n: i64) -> anyhow::Result<Vec<Todo>> {
+            let mut todos = Vec::from_iter(self.read_store_ref().values().cloned());
+            todos.shuffle(&mut rand::thread_rng());
+            todos.truncate(n as usize);
+            Ok(todos)
+        }
+
+        async fn modified_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+            let store = self.read_store_ref();
+            let mut todos: Vec<Todo> = store
+                .values()
+                .filter(|todo| todo.updated_at > since)
+                .cloned()
+                .collect();
+            todos.sort_by_key(|todo| (todo.updated_at, todo.id));
+            Ok(todos)
+        }
+
+        async fn completed_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+            let store = self.read_store_ref();
+            let mut todos: Vec<Todo> = store
+                .values()
+                .filter(|todo| todo.completed && (from..to).contains(&todo.updated_at))
+                .cloned()
+                .collect();
+            todos.sort_by_key(|todo| (todo.updated_at, todo.id));
+            Ok(todos)
+        }
+
+        async fn deleted_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<i32>> {
+            let audit = self.audit.read().unwrap();
+            Ok(audit
+                .iter()
+                .filter(|entry| entry.action == AuditLog::DELETE && entry.at > since)
+                .map(|entry| entry.todo_id)
+                .collect())
+        }
+
+        async fn revision(&self) -> anyhow::Result<i64> {
+            Ok(self.revision.load(Ordering::SeqCst))
+        }
+
+        fn stream_all(&self) -> BoxStream<'static, anyhow::Result<Todo>> {
+            let todos = self.sorted();
+            Box::pin(futures::stream::iter(todos.into_iter().map(Ok)))
+        }
+
+        async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo> {
+            let mut store = self.write_store_ref();
+            let todo = store.get(&id).context(RepositoryError::NotFound(id))?;
+            let text = payload.text.unwrap_or_else(|| todo.text.clone());
+            let completed = payload.completed.unwrap_or(todo.completed);
+            Self::check_open_text(&store, id, &text, completed)?;
+            // reopening takes the todo back out of the archive, as `updateTodo.sql` does
+            let todo = Todo {
+                text,
+                completed,
+                archived: todo.archived && completed,
+                updated_at: Utc::now(),
+                ..todo.clone()
+            };
+            store.insert(id, todo.clone());
+            self.record(AuditLog::UPDATE, &todo);
+            Ok(todo)
+        }
+
+        async fn duplicate(&self, id: i32) -> anyhow::Result<Todo> {
+            let source = self.find(id).await?;
+            let text = {
+                let store = self.read_store_ref();
+                (1..)
+                    .map(|n| copy_text(&source.text, n))
+                    .find(|text| Self::check_open_text(&store, 0, text, false).is_ok())
+                    .unwrap()
+            };
+            self.create(CreateTodo::new(text)).await
+        }
+
+        async fn create_if_absent(&self, payload: CreateTodo) -> anyhow::Result<CreatedIfAbsent> {
+            let existing = self
+                .read_store_ref()
+                .values()
+                .find(|todo| !todo.completed && todo.text == payload.text)
+                .cloned();
+            match existing {
+                Some(todo) => Ok(CreatedIfAbsent::Existing(todo)),
+                None => Ok(CreatedIfAbsent::Created(self.create(payload).await?)),
+            }
+        }
+
+        // One guard covers the lookup and the write, as `upsertTodo.sql` is a single statement.
+        async fn upsert(&self, uuid: Uuid, payload: UpsertTodo) -> anyhow::Result<Upserted> {
+            let mut store = self.write_store_ref();
+            let existing = store.values().find(|todo| todo.uuid == uuid).cloned();
+            let id = existing
+                .as_ref()
+                .map_or_else(|| store.keys().max().unwrap_or(&0) + 1, |todo| todo.id);
+            Self::check_open_text(&store, id, &payload.text, payload.completed)?;
+            let upserted = match existing {
+                Some(todo) => Upserted::Updated(Todo {
+                    text: payload.text,
+                    completed: payload.completed,
+                    archived: todo.archived && payload.completed,
+                    updated_at: Utc::now(),
+                    ..todo
+                }),
+                None => Upserted::Created(Todo {
+                    uuid,
+                    completed: payload.completed,
+                    position: store.values().map(|todo| todo.position).max().unwrap_or(0) + 1,
+                    updated_at: Utc::now(),
+                    ..Todo::new(id, payload.text)
+                }),
+            };
+            let (action, todo) = match &upserted {
+                Upserted::Created(todo) => (AuditLog::CREATE, todo),
+                Upserted::Updated(todo) => (AuditLog::UPDATE, todo),
+            };
+            store.insert(id, todo.clone());
+            self.record(action, todo);
+            Ok(upserted)
+        }
+
+        async fn reorder(&self, id: i32, position: i32) -> anyhow::Result<Reordered> {
+            let mut store = self.write_store_ref();
+            let current = store.get(&id).context(RepositoryError::NotFound(id))?.position;
+            let position = position.clamp(1, store.len() as i32);
+            let mut shifted = Vec::new();
+            for todo in store.values_mut() {
+                if todo.id == id {
+                    todo.position = position;
+                    continue;
+                } else if position < current && (position..current).contains(&todo.position) {
+                    todo.position += 1;
+                } else if current < position && (current + 1..=position).contains(&todo.position) {
+                    todo.position -= 1;
+                } else {
+                    continue;
+                }
+                shifted.push(todo.clone());
+            }
+            shifted.sort_by_key(|todo| (todo.position, todo.id));
+            let todo = store[&id].clone();
+            for todo in std::iter::once(&todo).chain(&shifted) {
+                self.record(AuditLog::UPDATE, todo);
+            }
+            Ok(Reordered { todo, shifted })
+        }
+
+        async fn swap(&self, id_a: i32, id_b: i32) -> anyhow::Result<(Todo, Todo)> {
+            let mut store = self.write_store_ref();
+            let position_a = store.get(&id_a).context(RepositoryError::NotFound(id_a))?.position;
+            let position_b = store.get(&id_b).context(RepositoryError::NotFound(id_b))?.position;
+            store.get_mut(&id_a).unwrap().position = position_b;
+            store.get_mut(&id_b).unwrap().position = position_a;
+            let swapped = (store[&id_a].clone(), store[&id_b].clone());
+            self.record(AuditLog::UPDATE, &swapped.0);
+            self.record(AuditLog::UPDATE, &swapped.1);
+            Ok(swapped)
+        }
+
+        // Applied to a copy first so a clash with the open-text index leaves the store untouched.
+        async fn set_all_completed(&self, completed: bool) -> anyhow::Result<Vec<Todo>> {
+            let mut store = self.write_store_ref();
+            let mut updated = store.clone();
+            let mut todos = Vec::new();
+            for todo in updated.values_mut().filter(|todo| todo.completed != completed) {
+                todo.completed = completed;
+                todo.archived &= completed;
+                todo.updated_at = Utc::now();
+                todos.push(todo.clone());
+            }
+            for todo in updated.values() {
+                Self::check_open_text(&updated, todo.id, &todo.text, todo.completed)?;
+            }
+            *store = updated;
+            todos.sort_by_key(|todo| todo.id);
+            for todo in &todos {
+                self.record(AuditLog::UPDATE, todo);
+            }
+            Ok(todos)
+        }
+
+        // Applied to a copy first so a clash with the open-text index leaves the store untouched.
+        async fn replace_text(&self, from: &str, to: &str) -> anyhow::Result<Vec<Todo>> {
+            let mut store = self.write_store_ref();
+            let mut replaced = store.clone();
+            let mut changed = Vec::new();
+            for todo in replaced.values_mut().filter(|todo| todo.text.contains(from)) {
+                todo.text = todo.text.replace(from, to);
+                todo.updated_at = Utc::now();
+                validate_text(todo)?;
+                changed.push(todo.clone());
+            }
+            for todo in replaced.values() {
+                Self::check_open_text(&replaced, todo.id, &todo.text, todo.completed)?;
+            }
+            *store = replaced;
+            changed.sort_by_key(|todo| todo.id);
+            for todo in &changed {
+                self.record(AuditLog::UPDATE, todo);
+            }
+            Ok(changed)
+        }
+
+        async fn archive_completed(&self, updated_before: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+            let mut store = self.write_store_ref();
+            let mut archived = Vec::new();
+            for todo in store.values_mut() {
+                if todo.completed && !todo.archived && todo.updated_at < updated_before {
+                    todo.archived = true;
+                    archived.push(todo.clone());
+                }
+            }
+            archived.sort_by_key(|todo| todo.id);
+            for todo in &archived {
+                self.record(AuditLog::UPDATE, todo);
+            }
+            Ok(archived)
+        }
+
+        // Like `replace_text`, the bundle lands on a copy that must pass the open-text check.
+        async fn import(&self, todos: Vec<Todo>) -> anyhow::Result<Vec<Upserted>> {
+            for todo in &todos {
+                validate_text(todo)?;
+            }
+            let mut store = self.write_store_ref();
+            let mut updated = store.clone();
+            let mut imported = Vec::new();
+            for todo in todos {
+                let upserted = if updated.contains_key(&todo.id) {
+                    Upserted::Updated(todo.clone())
+                } else {
+                    Upserted::Created(todo.clone())
+                };
+                updated.insert(todo.id, todo);
+                imported.push(upserted);
+            }
+            for todo in updated.values() {
+                Self::check_open_text(&updated, todo.id, &todo.text, todo.completed)?;
+            }
+            *store = updated;
+
+            let mut order: Vec<(i32, i32)> = store.values().map(|todo| (todo.position, todo.id)).collect();
+            order.sort_unstable();
+            let mut renumbered = Vec::new();
+            for (position, (_, id)) in (1..).zip(order) {
+                let todo = store.get_mut(&id).unwrap();
+                if todo.position != position {
+                    todo.position = position;
+                    renumbered.push(todo.clone());
+                }
+            }
+
+            let imported = Upserted::with_renumbered(imported, renumbered);
+            for upserted in &imported {
+                match upserted {
+                    Upserted::Created(todo) => self.record(AuditLog::CREATE, todo),
+                    Upserted::Updated(todo) => self.record(AuditLog::UPDATE, todo),
+                }
+            }
+            Ok(imported)
+        }
+
+        async fn delete(&self, id: i32) -> anyhow::Result<()> {
+            let mut store = self.write_store_ref();
+            let todo = store.remove(&id).ok_or(RepositoryError::NotFound(id))?;
+            for other in store.values_mut().filter(|other| other.position > todo.position) {
+                other.position -= 1;
+            }
+            self.record(AuditLog::DELETE, &todo);
+            Ok(())
+        }
+
+        async fn history(&self, id: i32) -> anyhow::Result<Vec<AuditLog>> {
+            let audit = self.audit.read().unwrap();
+            Ok(audit.iter().filter(|entry| entry.todo_id == id).cloned().collect())
+        }
+
+        // there is no schema to fall behind on
+        async fn pending_migrations(&self) -> anyhow::Result<Vec<i64>> {
+            Ok(Vec::new())
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use axum::async_trait;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::*;
+    pub use super::memory::TodoRepositoryForMemory;
+
+    impl Todo {
+        // Generated values never match a hand-built expectation, so tests compare with them cleared.
+        pub fn without_generated(self) -> Self {
+            Self {
+                uuid: Uuid::nil(),
+                position: 0,
+                updated_at: DateTime::default(),
+                ..self
+            }
+        }
+    }
+
+    impl CreateTodo {
+        pub fn with_uuid(text: String, uuid: Uuid) -> Self {
+            Self {
+                text,
+                uuid: Some(uuid),
+            }
+        }
+    }
+
+    impl UpsertTodo {
+        pub fn new(text: String, completed: bool) -> Self {
+            Self { text, completed }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum MockResponse {
+        Fail(RepositoryError),
+        Panic(&'static str),
+    }
+
+    // Drives handlers down their error paths; every method answers with what was configured
+    // for its name, and an unconfigured method fails as `Unexpected`.
+    #[derive(Debug, Clone, Default)]
+    pub struct MockTodoRepository {
+        responses: Arc<Mutex<HashMap<&'static str, MockResponse>>>,
+        delays: Arc<Mutex<HashMap<&'static str, Duration>>>,
+        calls: Arc<Mutex<HashMap<&'static str, usize>>>,
+    }
+
+    impl MockTodoRepository {
+        pub fn new() -> Self {
+            MockTodoRepository::default()
+        }
+
+        pub fn fail(self, method: &'static str, error: RepositoryError) -> Self {
+            self.responses.lock().unwrap().insert(method, MockResponse::Fail(error));
+            self
+        }
+
+        pub fn panic(self, method: &'static str, message: &'static str) -> Self {
+            self.responses.lock().unwrap().insert(method, MockResponse::Panic(message));
+            self
+        }
+
+        // Sleeps before answering, so tests can hold a request open inside a handler.
+        pub fn slow(self, method: &'static str, delay: Duration) -> Self {
+            self.delays.lock().unwrap().insert(method, delay);
+            self
+        }
+
+        pub fn calls(&self, method: &'static str) -> usize {
+            self.calls.lock().unwrap().get(method).copied().unwrap_or(0)
+        }
+
+        async fn respond<R>(&self, method: &'static str) -> anyhow::Result<R> {
+            *self.calls.lock().unwrap().entry(method).or_default() += 1;
+            let delay = self.delays.lock().unwrap().get(method).copied();
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            let response = self.responses.lock().unwrap().get(method).cloned();
+            match response {
+                Some(MockResponse::Fail(error)) => Err(error.into()),
+                Some(MockResponse::Panic(message)) => panic!("{}", message),
+                None => Err(RepositoryError::Unexpected(format!("{} is not configured", method)).into()),
+            }
+        }
     }
 
     #[async_trait]
-    impl TodoRepository for TodoRepositoryForMemory {
-        async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo> {
-            let mut store = self.write_store_ref();
-            let id = (store.len() + 1) as i32;
-            let todo = Todo::new(id, payload.text);
-            store.insert(id, todo.clone());
-            Ok(todo)
+    impl TodoRepository for MockTodoRepository {
+        async fn create(&self, _payload: CreateTodo) -> anyhow::Result<Todo> {
+            self.respond("create").await
         }
 
-        async fn find(&self, id: i32) -> anyhow::Result<Todo> {
-            let store = self.read_store_ref();
-            let todo = store
-                .get(&id)
-                .cloned()
-                .ok_or(RepositoryError::NotFound(id))?;
-            Ok(todo)
+        async fn create_if_absent(&self, _payload: CreateTodo) -> anyhow::Result<CreatedIfAbsent> {
+            self.respond("create_if_absent").await
+        }
+
+        async fn find(&self, _id: i32) -> anyhow::Result<Todo> {
+            self.respond("find").await
+        }
+
+        async fn exists(&self, _id: i32) -> anyhow::Result<bool> {
+            self.respond("exists").await
+        }
+
+        async fn count_where(&self, _filter: TodoFilter) -> anyhow::Result<i64> {
+            self.respond("count_where").await
+        }
+
+        async fn progress(&self) -> anyhow::Result<TodoProgress> {
+            self.respond("progress").await
+        }
+
+        async fn find_by_uuid(&self, _uuid: Uuid) -> anyhow::Result<Option<Todo>> {
+            self.respond("find_by_uuid").await
+        }
+
+        async fn find_many_by_uuid(&self, _uuids: &[Uuid]) -> anyhow::Result<Vec<Todo>> {
+            self.respond("find_many_by_uuid").await
         }
 
         async fn all(&self) -> anyhow::Result<Vec<Todo>> {
-            let store = self.read_store_ref();
-            Ok(Vec::from_iter(store.values().cloned()))
+            self.respond("all").await
         }
 
-        async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo> {
-            let mut store = self.write_store_ref();
-            let todo = store.get(&id).context(RepositoryError::NotFound(id))?;
-            let text = payload.text.unwrap_or_else(|| todo.text.clone());
-            let completed = payload.completed.unwrap_or(todo.completed);
-            let todo = Todo {
-                id,
-                text,
-                completed,
-            };
-            store.insert(id, todo.clone());
-            Ok(todo)
+        async fn snapshot(&self, _limit: i64, _offset: i64, _order: SortOrder) -> anyhow::Result<TodoSnapshot> {
+            self.respond("snapshot").await
         }
 
-        async fn delete(&self, id: i32) -> anyhow::Result<()> {
-            let mut store = self.write_store_ref();
-            store.remove(&id).ok_or(RepositoryError::NotFound(id))?;
-            Ok(())
+        async fn page(&self, _limit: i64, _offset: i64, _order: SortOrder) -> anyhow::Result<Vec<Todo>> {
+            self.respond("page").await
+        }
+
+        async fn latest(&self) -> anyhow::Result<Option<Todo>> {
+            self.respond("latest").await
+        }
+
+        async fn oldest_open(&self) -> anyhow::Result<Option<Todo>> {
+            self.respond("oldest_open").await
+        }
+
+        async fn top_open(&self, _n: i64) -> anyhow::Result<Vec<Todo>> {
+            self.respond("top_open").await
+        }
+
+        async fn sample(&self, _n: i64) -> anyhow::Result<Vec<Todo>> {
+            self.respond("sample").await
+        }
+
+        async fn window(&self, _around_id: i32, _radius: i64) -> anyhow::Result<Vec<Todo>> {
+            self.respond("window").await
+        }
+
+        async fn completed_between(&self, _from: DateTime<Utc>, _to: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+            self.respond("completed_between").await
+        }
+
+        async fn find_by_text(&self, _text: &str) -> anyhow::Result<Vec<Todo>> {
+            self.respond("find_by_text").await
+        }
+
+        async fn modified_since(&self, _since: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+            self.respond("modified_since").await
+        }
+
+        async fn deleted_since(&self, _since: DateTime<Utc>) -> anyhow::Result<Vec<i32>> {
+            self.respond("deleted_since").await
+        }
+
+        async fn revision(&self) -> anyhow::Result<i64> {
+            self.respond("revision").await
+        }
+
+        fn stream_all(&self) -> BoxStream<'static, anyhow::Result<Todo>> {
+            let mock = self.clone();
+            Box::pin(futures::stream::once(async move { mock.respond("stream_all").await }))
+        }
+
+        async fn update(&self, _id: i32, _payload: UpdateTodo) -> anyhow::Result<Todo> {
+            self.respond("update").await
+        }
+
+        async fn duplicate(&self, _id: i32) -> anyhow::Result<Todo> {
+            self.respond("duplicate").await
+        }
+
+        async fn upsert(&self, _uuid: Uuid, _payload: UpsertTodo) -> anyhow::Result<Upserted> {
+            self.respond("upsert").await
+        }
+
+        async fn reorder(&self, _id: i32, _position: i32) -> anyhow::Result<Reordered> {
+            self.respond("reorder").await
+        }
+
+        async fn swap(&self, _id_a: i32, _id_b: i32) -> anyhow::Result<(Todo, Todo)> {
+            self.respond("swap").await
+        }
+
+        async fn set_all_completed(&self, _completed: bool) -> anyhow::Result<Vec<Todo>> {
+            self.respond("set_all_completed").await
+        }
+
+        async fn replace_text(&self, _from: &str, _to: &str) -> anyhow::Result<Vec<Todo>> {
+            self.respond("replace_text").await
+        }
+
+        async fn archive_completed(&self, _updated_before: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+            self.respond("archive_completed").await
+        }
+
+        async fn import(&self, _todos: Vec<Todo>) -> anyhow::Result<Vec<Upserted>> {
+            self.respond("import").await
+        }
+
+        async fn delete(&self, _id: i32) -> anyhow::Result<()> {
+            self.respond("delete").await
+        }
+
+        async fn history(&self, _id: i32) -> anyhow::Result<Vec<AuditLog>> {
+            self.respond("history").await
+        }
+
+        async fn pending_migrations(&self) -> anyhow::Result<Vec<i64>> {
+            self.respond("pending_migrations").await
         }
     }
 
     #[cfg(test)]
     mod test {
         use super::*;
+        use chrono::TimeZone;
+
+        #[test]
+        fn copy_text_fits_the_length_limit() {
+            let max = text_len::DEFAULT_MAX_TEXT_LEN;
+            let copy = copy_text(&"a".repeat(max), 12);
+            assert_eq!(max, copy.chars().count());
+            assert!(copy.ends_with("a (copy 12)"));
+        }
 
         #[tokio::test]
         async fn todo_crud_scenario() {
@@ -365,19 +2876,27 @@ pub mod test_utils {
 
             // create
             let repository = TodoRepositoryForMemory::new();
-            let todo = repository
-                .create(CreateTodo { text })
+            let created = repository
+                .create(CreateTodo::new(text))
                 .await
                 .expect("failed create todo");
-            assert_eq!(expected, todo);
+            assert_eq!(expected, created.clone().without_generated());
 
             // find
-            let todo = repository.find(todo.id).await.unwrap();
-            assert_eq!(expected, todo);
+            let todo = repository.find(created.id).await.unwrap();
+            assert_eq!(created, todo);
+
+            // find_by_uuid
+            let todo = repository.find_by_uuid(created.uuid).await.unwrap();
+            assert_eq!(Some(created.clone()), todo);
+
+            // find_many_by_uuid
+            let todos = repository.find_many_by_uuid(&[Uuid::new_v4(), created.uuid]).await.unwrap();
+            assert_eq!(vec![created.clone()], todos);
 
             // all
             let todo = repository.all().await.expect("failed get all todo");
-            assert_eq!(vec![expected], todo);
+            assert_eq!(vec![created.clone()], todo);
 
             // update
             let text = "update todo text".to_string();
@@ -396,13 +2915,402 @@ pub mod test_utils {
                     id,
                     text,
                     completed: true,
+                    uuid: created.uuid,
+                    position: created.position,
+                    updated_at: todo.updated_at,
+                    archived: false,
                 },
                 todo
             );
+            assert!(todo.updated_at >= created.updated_at);
 
             // delete
             let res = repository.delete(id).await;
             assert!(res.is_ok())
         }
+
+        async fn create_todos(repository: &TodoRepositoryForMemory, texts: &[&str]) {
+            for text in texts {
+                repository
+                    .create(CreateTodo::new(text.to_string()))
+                    .await
+                    .expect("failed create todo");
+            }
+        }
+
+        async fn all_texts(repository: &TodoRepositoryForMemory) -> Vec<String> {
+            let todos = repository.all().await.expect("failed get all todo");
+            todos.into_iter().map(|todo| todo.text).collect()
+        }
+
+        #[tokio::test]
+        async fn todo_upsert_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            let uuid = Uuid::new_v4();
+
+            let created = match repository.upsert(uuid, UpsertTodo::new("a".to_string(), false)).await.unwrap() {
+                Upserted::Created(todo) => todo,
+                other => panic!("expected insert, got {:?}", other),
+            };
+            assert_eq!(uuid, created.uuid);
+
+            let updated = match repository.upsert(uuid, UpsertTodo::new("b".to_string(), true)).await.unwrap() {
+                Upserted::Updated(todo) => todo,
+                other => panic!("expected update, got {:?}", other),
+            };
+            assert_eq!(created.id, updated.id);
+            assert_eq!("b", updated.text);
+            assert!(updated.completed);
+        }
+
+        #[tokio::test]
+        async fn todo_upsert_creates_completed_todo_in_one_write() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a"]).await;
+
+            // the open "a" doesn't clash, since the upserted row is completed
+            let uuid = Uuid::new_v4();
+            let todo = match repository.upsert(uuid, UpsertTodo::new("a".to_string(), true)).await.unwrap() {
+                Upserted::Created(todo) => todo,
+                other => panic!("expected insert, got {:?}", other),
+            };
+            assert!(todo.completed);
+            let actions: Vec<String> = repository.history(todo.id).await.unwrap().into_iter().map(|entry| entry.action).collect();
+            assert_eq!(vec![AuditLog::CREATE.to_string()], actions);
+        }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn todo_concurrent_upserts_create_once() {
+            let repository = TodoRepositoryForMemory::new();
+            let uuid = Uuid::new_v4();
+            let pushes = (0..8).map(|i| {
+                let repository = repository.clone();
+                tokio::spawn(async move { repository.upsert(uuid, UpsertTodo::new(format!("push {}", i), false)).await })
+            });
+            let results = futures::future::join_all(pushes).await;
+
+            let created = results
+                .into_iter()
+                .map(|res| res.unwrap().expect("failed upsert todo"))
+                .filter(|upserted| matches!(upserted, Upserted::Created(_)))
+                .count();
+            assert_eq!(1, created);
+            assert_eq!(1, repository.all().await.unwrap().len());
+        }
+
+        #[tokio::test]
+        async fn todo_reorder_up_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c", "d"]).await;
+
+            let reordered = repository.reorder(4, 1).await.expect("failed reorder todo");
+            assert_eq!(1, reordered.todo.position);
+            let shifted: Vec<(i32, i32)> = reordered.shifted.iter().map(|todo| (todo.id, todo.position)).collect();
+            assert_eq!(vec![(1, 2), (2, 3), (3, 4)], shifted);
+            assert_eq!(vec!["d", "a", "b", "c"], all_texts(&repository).await);
+        }
+
+        #[tokio::test]
+        async fn todo_bulk_and_positional_mutators_are_audited() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c"]).await;
+            let updates = |id: i32| {
+                let repository = repository.clone();
+                async move {
+                    repository.history(id).await.unwrap().iter().filter(|entry| entry.action == AuditLog::UPDATE).count()
+                }
+            };
+
+            repository.reorder(3, 1).await.expect("failed reorder todo");
+            assert_eq!(vec![1, 1, 1], vec![updates(1).await, updates(2).await, updates(3).await]);
+            repository.bump(2, SortOrder::Asc).await.expect("failed bump todo");
+            repository.swap(1, 3).await.expect("failed swap todo");
+            assert_eq!(vec![3, 2, 3], vec![updates(1).await, updates(2).await, updates(3).await]);
+
+            repository.set_all_completed(true).await.expect("failed complete all");
+            repository.archive_completed(Utc::now() + chrono::Duration::days(1)).await.expect("failed archive");
+            assert_eq!(vec![5, 4, 5], vec![updates(1).await, updates(2).await, updates(3).await]);
+
+            let copy = repository.duplicate(1).await.expect("failed duplicate todo");
+            assert_eq!(AuditLog::CREATE, repository.history(copy.id).await.unwrap()[0].action);
+
+            let last = repository.history(2).await.unwrap().pop().unwrap();
+            assert_eq!(true, last.payload_json["archived"]);
+        }
+
+        #[tokio::test]
+        async fn todo_swap_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c"]).await;
+
+            let (a, c) = repository.swap(1, 3).await.expect("failed swap todo");
+            assert_eq!((3, 1), (a.position, c.position));
+            assert_eq!(vec!["c", "b", "a"], all_texts(&repository).await);
+
+            let res = repository.swap(2, 99).await;
+            assert!(matches!(
+                res.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::NotFound(99))
+            ));
+            assert_eq!(vec!["c", "b", "a"], all_texts(&repository).await);
+        }
+
+        #[tokio::test]
+        async fn todo_reorder_down_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c", "d"]).await;
+
+            let reordered = repository.reorder(1, 3).await.expect("failed reorder todo");
+            assert_eq!(3, reordered.todo.position);
+            assert_eq!(vec![2, 3], reordered.shifted.iter().map(|todo| todo.id).collect::<Vec<_>>());
+            assert_eq!(vec!["b", "c", "a", "d"], all_texts(&repository).await);
+
+            // positions past the end clamp to the last slot
+            let reordered = repository.reorder(2, 100).await.expect("failed reorder todo");
+            assert_eq!(4, reordered.todo.position);
+            assert_eq!(vec!["c", "a", "d", "b"], all_texts(&repository).await);
+        }
+
+        #[tokio::test]
+        async fn todo_delete_keeps_positions_contiguous() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c"]).await;
+
+            repository.delete(1).await.expect("failed delete todo");
+            let positions: Vec<i32> = repository
+                .all()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|todo| todo.position)
+                .collect();
+            assert_eq!(vec![1, 2], positions);
+        }
+
+        #[tokio::test]
+        async fn todo_all_breaks_position_ties_by_id() {
+            let repository = TodoRepositoryForMemory::new();
+            let todos = [7, 3, 5, 1].map(|id| Todo {
+                position: 1,
+                ..Todo::new(id, format!("todo {}", id))
+            });
+            repository.import(todos.to_vec()).await.expect("failed import todo");
+
+            let ids: Vec<i32> = repository.all().await.unwrap().into_iter().map(|todo| todo.id).collect();
+            assert_eq!(vec![1, 3, 5, 7], ids);
+            let streamed: Vec<Todo> = repository.stream_all().try_collect().await.unwrap();
+            assert_eq!(ids, streamed.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+        }
+
+        #[tokio::test]
+        async fn todo_page_breaks_position_ties_by_id() {
+            let repository = TodoRepositoryForMemory::new();
+            let todos = (1..=7).rev().map(|id| Todo {
+                position: 1,
+                ..Todo::new(id, format!("todo {}", id))
+            });
+            repository.import(todos.collect()).await.expect("failed import todo");
+
+            for (order, expected) in [(SortOrder::Asc, vec![1, 2, 3, 4, 5, 6, 7]), (SortOrder::Desc, vec![7, 6, 5, 4, 3, 2, 1])] {
+                let mut paged = vec![];
+                for offset in [0, 3, 6] {
+                    let page = repository.page(3, offset, order).await.unwrap();
+                    paged.extend(page.into_iter().map(|todo| todo.id));
+                }
+                assert_eq!(expected, paged);
+            }
+        }
+
+        #[tokio::test]
+        async fn todo_open_text_unique_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b"]).await;
+
+            let res = repository.create(CreateTodo::new("a".to_string())).await;
+            assert!(matches!(
+                res.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::Conflict(_))
+            ));
+            let res = repository.update(2, UpdateTodo::text("a".to_string())).await;
+            assert!(matches!(
+                res.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::Conflict(_))
+            ));
+
+            repository
+                .update(1, UpdateTodo { text: None, completed: Some(true) })
+                .await
+                .expect("failed update todo");
+            repository
+                .create(CreateTodo::new("a".to_string()))
+                .await
+                .expect("failed create todo");
+            assert_eq!(vec!["a", "b", "a"], all_texts(&repository).await);
+        }
+
+        #[tokio::test]
+        async fn todo_uncomplete_all_rejects_clashing_open_text() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a"]).await;
+            repository
+                .update(1, UpdateTodo { text: None, completed: Some(true) })
+                .await
+                .expect("failed update todo");
+            create_todos(&repository, &["a"]).await;
+
+            let res = repository.set_all_completed(false).await;
+            assert!(matches!(
+                res.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::Conflict(_))
+            ));
+            assert!(repository.find(1).await.unwrap().completed);
+        }
+
+        #[tokio::test]
+        async fn todo_import_rejects_clashing_open_text() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a"]).await;
+
+            let bundle = vec![Todo { position: 2, ..Todo::new(2, "a".to_string()) }];
+            let res = repository.import(bundle).await;
+            assert!(matches!(
+                res.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::Conflict(_))
+            ));
+            assert_eq!(vec!["a"], all_texts(&repository).await);
+        }
+
+        #[tokio::test]
+        async fn todo_find_by_text_is_exact() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["milk", "Milk", "milk ", "bread"]).await;
+
+            let found = repository.find_by_text("milk").await.unwrap();
+            assert_eq!(vec![1], found.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+            assert!(repository.find_by_text("mil").await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn todo_window_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c", "d", "e"]).await;
+            let ids = |todos: Vec<Todo>| todos.into_iter().map(|todo| todo.id).collect::<Vec<_>>();
+
+            assert_eq!(vec![2, 3, 4], ids(repository.window(3, 1).await.unwrap()));
+            assert_eq!(vec![1, 2, 3], ids(repository.window(1, 2).await.unwrap()));
+            assert_eq!(vec![4, 5], ids(repository.window(5, 1).await.unwrap()));
+            assert_eq!(vec![1, 2, 3, 4, 5], ids(repository.window(3, i64::MAX).await.unwrap()));
+            assert_eq!(vec![3], ids(repository.window(3, 0).await.unwrap()));
+        }
+
+        #[tokio::test]
+        async fn todo_completed_between_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            let at = |hour: i64| Utc.timestamp_opt(1_600_000_000 + hour * 3600, 0).unwrap();
+            let todos = [(1, true, 8), (2, true, 10), (3, false, 11), (4, true, 12), (5, true, 13)].map(|(id, completed, hour)| Todo {
+                completed,
+                updated_at: at(hour),
+                position: id,
+                ..Todo::new(id, format!("todo {}", id))
+            });
+            repository.import(todos.to_vec()).await.expect("failed import todo");
+
+            let completed = repository.completed_between(at(10), at(13)).await.unwrap();
+            assert_eq!(vec![2, 4], completed.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+        }
+
+        #[tokio::test]
+        async fn todo_reopening_leaves_the_archive() {
+            let repository = TodoRepositoryForMemory::new();
+            let old = Utc::now() - chrono::Duration::days(40);
+            let todos = [1, 2].map(|id| Todo {
+                completed: true,
+                updated_at: old,
+                position: id,
+                ..Todo::new(id, format!("todo {}", id))
+            });
+            repository.import(todos.to_vec()).await.expect("failed import todo");
+            repository.archive_completed(Utc::now()).await.expect("failed archive todo");
+            assert!(repository.page(10, 0, SortOrder::Asc).await.unwrap().is_empty());
+
+            repository
+                .update(1, UpdateTodo { text: None, completed: Some(false) })
+                .await
+                .expect("failed update todo");
+            let listed = repository.page(10, 0, SortOrder::Asc).await.unwrap();
+            assert_eq!(vec![1], listed.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+
+            repository.set_all_completed(false).await.expect("failed uncomplete all");
+            let listed = repository.page(10, 0, SortOrder::Asc).await.unwrap();
+            assert_eq!(vec![1, 2], listed.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+        }
+
+        #[tokio::test]
+        async fn todo_set_all_completed_skips_unchanged_todos() {
+            let repository = TodoRepositoryForMemory::new();
+            let old = Utc::now() - chrono::Duration::days(40);
+            let todos = [(1, true), (2, false)].map(|(id, completed)| Todo {
+                completed,
+                updated_at: old,
+                position: id,
+                ..Todo::new(id, format!("todo {}", id))
+            });
+            repository.import(todos.to_vec()).await.expect("failed import todo");
+
+            let changed = repository.set_all_completed(true).await.expect("failed complete all");
+            assert_eq!(vec![2], changed.into_iter().map(|todo| todo.id).collect::<Vec<_>>());
+            assert_eq!(old, repository.find(1).await.unwrap().updated_at);
+            assert!(repository.history(1).await.unwrap().iter().all(|entry| entry.action != AuditLog::UPDATE));
+        }
+
+        #[tokio::test]
+        async fn todo_top_open_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c", "d"]).await;
+            repository
+                .update(2, UpdateTodo { text: None, completed: Some(true) })
+                .await
+                .expect("failed update todo");
+            repository.reorder(4, 1).await.expect("failed reorder todo");
+
+            let texts = |todos: Vec<Todo>| todos.into_iter().map(|todo| todo.text).collect::<Vec<_>>();
+            assert_eq!(vec!["d", "a"], texts(repository.top_open(2).await.unwrap()));
+            assert_eq!(vec!["d", "a", "c"], texts(repository.top_open(10).await.unwrap()));
+        }
+
+        #[tokio::test]
+        async fn todo_count_where_scenario() {
+            let repository = TodoRepositoryForMemory::new();
+            create_todos(&repository, &["a", "b", "c"]).await;
+            repository
+                .update(2, UpdateTodo { text: None, completed: Some(true) })
+                .await
+                .expect("failed update todo");
+
+            let count = |completed| repository.count_where(TodoFilter { completed });
+            assert_eq!(1, count(Some(true)).await.unwrap());
+            assert_eq!(2, count(Some(false)).await.unwrap());
+            assert_eq!(3, repository.count_where(TodoFilter::default()).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn todo_duplicate_uuid_scenario() {
+            let uuid = Uuid::new_v4();
+            let repository = TodoRepositoryForMemory::new();
+            let created = repository
+                .create(CreateTodo::with_uuid("todo text".to_string(), uuid))
+                .await
+                .expect("failed create todo");
+            assert_eq!(uuid, created.uuid);
+
+            let res = repository
+                .create(CreateTodo::with_uuid("retried todo text".to_string(), uuid))
+                .await;
+            assert!(matches!(
+                res.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::Duplicate(duplicate)) if *duplicate == uuid
+            ));
+            assert_eq!(vec![created], repository.all().await.unwrap());
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::repositories::Todo;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// What a share link reveals: the id, position and archive flag stay private to the owner's list.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SharedTodo {
+    pub uuid: Uuid,
+    pub text: String,
+    pub completed: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Todo> for SharedTodo {
+    fn from(todo: Todo) -> Self {
+        SharedTodo {
+            uuid: todo.uuid,
+            text: todo.text,
+            completed: todo.completed,
+            updated_at: todo.updated_at,
+        }
+    }
+}
+
+// Tokens are `<payload>.<signature>`, both URL-safe base64 without padding. The payload is a
+// snapshot, so a link keeps showing the todo as it was when shared.
+#[derive(Clone)]
+pub struct ShareSigner {
+    key: Arc<Vec<u8>>,
+}
+
+impl ShareSigner {
+    pub fn from_secret(secret: Option<&str>) -> Self {
+        let key = match secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                tracing::warn!("JWT_SECRET is unset, share links will not survive a restart");
+                let mut key = vec![0; 32];
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+        ShareSigner { key: Arc::new(key) }
+    }
+
+    pub fn sign(&self, todo: &SharedTodo) -> anyhow::Result<String> {
+        let payload = serde_json::to_vec(todo)?;
+        let signature = self.mac(&payload).finalize().into_bytes();
+        Ok(format!("{}.{}", encode(&payload), encode(&signature)))
+    }
+
+    // `None` for anything that is malformed or was not signed with this key.
+    pub fn verify(&self, token: &str) -> Option<SharedTodo> {
+        let (payload, signature) = token.split_once('.')?;
+        let payload = decode(payload)?;
+        self.mac(&payload).verify_slice(&decode(signature)?).ok()?;
+        serde_json::from_slice(&payload).ok()
+    }
+
+    fn mac(&self, payload: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    base64::decode_config(text, base64::URL_SAFE_NO_PAD).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn shared() -> SharedTodo {
+        Todo::new(1, "share me".to_string()).into()
+    }
+
+    #[test]
+    fn valid_token_decodes_to_the_original_todo() {
+        let signer = ShareSigner::from_secret(Some("secret"));
+        let token = signer.sign(&shared()).unwrap();
+        assert!(!token.contains(['+', '/', '=']), "{}", token);
+        assert_eq!(Some(shared()), signer.verify(&token));
+    }
+
+    #[test]
+    fn tampered_token_is_rejected() {
+        let signer = ShareSigner::from_secret(Some("secret"));
+        let token = signer.sign(&shared()).unwrap();
+        let (_, signature) = token.split_once('.').unwrap();
+
+        let forged = SharedTodo {
+            completed: true,
+            ..shared()
+        };
+        let forged = format!("{}.{}", encode(&serde_json::to_vec(&forged).unwrap()), signature);
+        assert_eq!(None, signer.verify(&forged));
+
+        assert_eq!(None, ShareSigner::from_secret(Some("other")).verify(&token));
+        assert_eq!(None, signer.verify("not-a-token"));
+    }
+}
@@ -0,0 +1,239 @@
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{
+    future::{BoxFuture, Shared},
+    stream::BoxStream,
+    FutureExt,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::repositories::{
+    AuditLog, CreateTodo, CreatedIfAbsent, Reordered, RepositoryError, SortOrder, Todo, TodoFilter,
+    TodoProgress, TodoRepository, TodoSnapshot, UpdateTodo, UpsertTodo, Upserted,
+};
+
+type Flight = Shared<BoxFuture<'static, Result<Todo, Arc<anyhow::Error>>>>;
+
+// Concurrent `find`s for one id share a single query to `inner`; every other method passes
+// straight through. A caller that joins a flight gets the row as of when that query started.
+#[derive(Clone)]
+pub struct SingleFlight<R> {
+    inner: R,
+    in_flight: Arc<Mutex<HashMap<i32, Flight>>>,
+}
+
+impl<R: TodoRepository> SingleFlight<R> {
+    pub fn new(inner: R) -> Self {
+        SingleFlight {
+            inner,
+            in_flight: Arc::default(),
+        }
+    }
+}
+
+// The shared error is behind an `Arc`, so a `RepositoryError` is cloned back out to keep its status.
+fn unshare(e: Arc<anyhow::Error>) -> anyhow::Error {
+    match e.downcast_ref::<RepositoryError>() {
+        Some(e) => e.clone().into(),
+        None => anyhow::anyhow!("{:#}", e),
+    }
+}
+
+#[async_trait]
+impl<R: TodoRepository> TodoRepository for SingleFlight<R> {
+    async fn create(&self, payload: CreateTodo) -> anyhow::Result<Todo> {
+        self.inner.create(payload).await
+    }
+
+    async fn create_if_absent(&self, payload: CreateTodo) -> anyhow::Result<CreatedIfAbsent> {
+        self.inner.create_if_absent(payload).await
+    }
+
+    async fn find(&self, id: i32) -> anyhow::Result<Todo> {
+        let flight = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| {
+                let inner = self.inner.clone();
+                let in_flight = self.in_flight.clone();
+                async move {
+                    let result = inner.find(id).await.map_err(Arc::new);
+                    in_flight.lock().unwrap().remove(&id);
+                    result
+                }
+                .boxed()
+                .shared()
+            })
+            .clone();
+        flight.await.map_err(unshare)
+    }
+
+    async fn find_by_uuid(&self, uuid: Uuid) -> anyhow::Result<Option<Todo>> {
+        self.inner.find_by_uuid(uuid).await
+    }
+
+    async fn find_many_by_uuid(&self, uuids: &[Uuid]) -> anyhow::Result<Vec<Todo>> {
+        self.inner.find_many_by_uuid(uuids).await
+    }
+
+    async fn find_by_text(&self, text: &str) -> anyhow::Result<Vec<Todo>> {
+        self.inner.find_by_text(text).await
+    }
+
+    async fn exists(&self, id: i32) -> anyhow::Result<bool> {
+        self.inner.exists(id).await
+    }
+
+    async fn count_where(&self, filter: TodoFilter) -> anyhow::Result<i64> {
+        self.inner.count_where(filter).await
+    }
+
+    async fn progress(&self) -> anyhow::Result<TodoProgress> {
+        self.inner.progress().await
+    }
+
+    async fn all(&self) -> anyhow::Result<Vec<Todo>> {
+        self.inner.all().await
+    }
+
+    async fn snapshot(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<TodoSnapshot> {
+        self.inner.snapshot(limit, offset, order).await
+    }
+
+    async fn page(&self, limit: i64, offset: i64, order: SortOrder) -> anyhow::Result<Vec<Todo>> {
+        self.inner.page(limit, offset, order).await
+    }
+
+    async fn window(&self, around_id: i32, radius: i64) -> anyhow::Result<Vec<Todo>> {
+        self.inner.window(around_id, radius).await
+    }
+
+    async fn latest(&self) -> anyhow::Result<Option<Todo>> {
+        self.inner.latest().await
+    }
+
+    async fn oldest_open(&self) -> anyhow::Result<Option<Todo>> {
+        self.inner.oldest_open().await
+    }
+
+    async fn top_open(&self, n: i64) -> anyhow::Result<Vec<Todo>> {
+        self.inner.top_open(n).await
+    }
+
+    async fn sample(&self, n: i64) -> anyhow::Result<Vec<Todo>> {
+        self.inner.sample(n).await
+    }
+
+    async fn completed_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        self.inner.completed_between(from, to).await
+    }
+
+    async fn modified_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        self.inner.modified_since(since).await
+    }
+
+    async fn deleted_since(&self, since: DateTime<Utc>) -> anyhow::Result<Vec<i32>> {
+        self.inner.deleted_since(since).await
+    }
+
+    async fn revision(&self) -> anyhow::Result<i64> {
+        self.inner.revision().await
+    }
+
+    fn stream_all(&self) -> BoxStream<'static, anyhow::Result<Todo>> {
+        self.inner.stream_all()
+    }
+
+    async fn update(&self, id: i32, payload: UpdateTodo) -> anyhow::Result<Todo> {
+        self.inner.update(id, payload).await
+    }
+
+    async fn duplicate(&self, id: i32) -> anyhow::Result<Todo> {
+        self.inner.duplicate(id).await
+    }
+
+    async fn upsert(&self, uuid: Uuid, payload: UpsertTodo) -> anyhow::Result<Upserted> {
+        self.inner.upsert(uuid, payload).await
+    }
+
+    async fn reorder(&self, id: i32, position: i32) -> anyhow::Result<Reordered> {
+        self.inner.reorder(id, position).await
+    }
+
+    async fn bump(&self, id: i32, order: SortOrder) -> anyhow::Result<Reordered> {
+        self.inner.bump(id, order).await
+    }
+
+    async fn swap(&self, id_a: i32, id_b: i32) -> anyhow::Result<(Todo, Todo)> {
+        self.inner.swap(id_a, id_b).await
+    }
+
+    async fn set_all_completed(&self, completed: bool) -> anyhow::Result<Vec<Todo>> {
+        self.inner.set_all_completed(completed).await
+    }
+
+    async fn replace_text(&self, from: &str, to: &str) -> anyhow::Result<Vec<Todo>> {
+        self.inner.replace_text(from, to).await
+    }
+
+    async fn archive_completed(&self, updated_before: DateTime<Utc>) -> anyhow::Result<Vec<Todo>> {
+        self.inner.archive_completed(updated_before).await
+    }
+
+    async fn import(&self, todos: Vec<Todo>) -> anyhow::Result<Vec<Upserted>> {
+        self.inner.import(todos).await
+    }
+
+    async fn delete(&self, id: i32) -> anyhow::Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn history(&self, id: i32) -> anyhow::Result<Vec<AuditLog>> {
+        self.inner.history(id).await
+    }
+
+    async fn pending_migrations(&self) -> anyhow::Result<Vec<i64>> {
+        self.inner.pending_migrations().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repositories::test_utils::{MockTodoRepository, TodoRepositoryForMemory};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn concurrent_finds_share_one_inner_call() {
+        let inner = MockTodoRepository::new()
+            .fail("find", RepositoryError::NotFound(1))
+            .slow("find", Duration::from_millis(50));
+        let repository = SingleFlight::new(inner.clone());
+
+        let results = futures::future::join_all((0..50).map(|_| repository.find(1))).await;
+        assert_eq!(1, inner.calls("find"));
+        for result in results {
+            assert!(matches!(
+                result.unwrap_err().downcast_ref::<RepositoryError>(),
+                Some(RepositoryError::NotFound(1))
+            ));
+        }
+
+        repository.find(1).await.unwrap_err();
+        assert_eq!(2, inner.calls("find"));
+    }
+
+    #[tokio::test]
+    async fn find_returns_the_inner_todo() {
+        let inner = TodoRepositoryForMemory::new();
+        let created = inner.create(CreateTodo::new("text".to_string())).await.unwrap();
+        let repository = SingleFlight::new(inner);
+        assert_eq!(created, repository.find(created.id).await.unwrap());
+    }
+}
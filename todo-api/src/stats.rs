@@ -0,0 +1,56 @@
+use axum::{
+    body::Body,
+    extract::{Extension, MatchedPath},
+    http::Request,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tower::util::MapRequestLayer;
+
+// Keyed by method and route template, so `/todos/1` and `/todos/2` count as one route.
+#[derive(Debug, Clone, Default)]
+pub struct RequestStats {
+    counts: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl RequestStats {
+    // Raw paths of requests no route matched would give every scanner probe its own key.
+    pub const UNMATCHED: &'static str = "<unmatched>";
+
+    pub fn record<B>(&self, req: &Request<B>) {
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(Self::UNMATCHED, MatchedPath::as_str);
+        let key = format!("{} {}", req.method(), route);
+        *self.counts.lock().unwrap().entry(key).or_insert(0) += 1;
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+}
+
+pub async fn stats(Extension(stats): Extension<RequestStats>) -> impl IntoResponse {
+    Json(stats.snapshot())
+}
+
+// Mounts `GET /admin/stats` and counts every route registered on `router` so far.
+pub fn mount(router: Router) -> Router {
+    let counter = RequestStats::default();
+    router
+        .route("/admin/stats", get(stats))
+        .layer(MapRequestLayer::new({
+            let counter = counter.clone();
+            move |req: Request<Body>| {
+                counter.record(&req);
+                req
+            }
+        }))
+        .layer(Extension(counter))
+}
@@ -0,0 +1,95 @@
+use serde::{de::Error, Deserialize, Deserializer};
+use std::future::Future;
+
+tokio::task_local! {
+    static FLEXIBLE_BOOLS: bool;
+}
+
+// Only request bodies go through here, so `Config::flexible_bools` is scoped to each request by
+// `create_app`; outside a request the loose spellings are accepted, as by default.
+pub async fn scope<F: Future>(enabled: bool, future: F) -> F::Output {
+    FLEXIBLE_BOOLS.scope(enabled, future).await
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    deserialize_with(FLEXIBLE_BOOLS.try_with(|enabled| *enabled).unwrap_or(true), deserializer)
+}
+
+// Loosely-typed clients send flags as `1` or `"true"`; anything beyond these spellings is refused,
+// and with `flexible` off only a JSON boolean is.
+pub fn deserialize_with<'de, D: Deserializer<'de>>(flexible: bool, deserializer: D) -> Result<bool, D::Error> {
+    if !flexible {
+        return bool::deserialize(deserializer);
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Number(i64),
+        String(String),
+    }
+
+    match Flag::deserialize(deserializer)? {
+        Flag::Bool(flag) => Ok(flag),
+        Flag::Number(1) => Ok(true),
+        Flag::Number(0) => Ok(false),
+        Flag::String(flag) if flag == "true" || flag == "1" => Ok(true),
+        Flag::String(flag) if flag == "false" || flag == "0" => Ok(false),
+        _ => Err(D::Error::custom(
+            r#"expected a boolean, 0 or 1, or one of "true", "false", "1", "0""#,
+        )),
+    }
+}
+
+// For `Option<bool>` fields; pair with `#[serde(default)]` so an absent key stays `None`.
+pub fn deserialize_option<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    struct Flag(#[serde(deserialize_with = "deserialize")] bool);
+
+    Ok(Option::<Flag>::deserialize(deserializer)?.map(|Flag(flag)| flag))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn deserialize_accepts_loose_spellings() {
+        for (value, expected) in [
+            (json!(true), true),
+            (json!(false), false),
+            (json!(1), true),
+            (json!(0), false),
+            (json!("true"), true),
+            (json!("false"), false),
+            (json!("1"), true),
+            (json!("0"), false),
+        ] {
+            assert_eq!(expected, deserialize(value.clone()).unwrap(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn deserialize_rejects_other_values() {
+        for value in [json!("yes"), json!(2), json!("TRUE"), json!(null)] {
+            assert!(deserialize(value.clone()).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn deserialize_with_strict_accepts_only_booleans() {
+        assert!(deserialize_with(false, json!(true)).unwrap());
+        assert!(!deserialize_with(false, json!(false)).unwrap());
+        for value in [json!(1), json!("true"), json!("0")] {
+            assert!(deserialize_with(false, value.clone()).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn deserialize_option_keeps_null() {
+        assert_eq!(None, deserialize_option(json!(null)).unwrap());
+        assert_eq!(Some(true), deserialize_option(json!("1")).unwrap());
+    }
+}
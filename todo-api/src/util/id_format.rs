@@ -0,0 +1,81 @@
+use serde::{Deserialize, Deserializer, Serializer};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+// Set once at startup from `Config::id_as_string`; serde attributes have no other way to see config.
+static ID_AS_STRING: AtomicBool = AtomicBool::new(false);
+
+tokio::task_local! {
+    static REQUEST_ID_AS_STRING: bool;
+}
+
+pub fn set_id_as_string(enabled: bool) {
+    ID_AS_STRING.store(enabled, Ordering::Relaxed);
+}
+
+// `create_app` runs each request under its own config; the global still covers ids serialized
+// outside a request, such as webhook deliveries.
+pub async fn scope<F: Future>(enabled: bool, future: F) -> F::Output {
+    REQUEST_ID_AS_STRING.scope(enabled, future).await
+}
+
+pub fn serialize<S: Serializer>(id: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+    let as_string = REQUEST_ID_AS_STRING
+        .try_with(|enabled| *enabled)
+        .unwrap_or_else(|_| ID_AS_STRING.load(Ordering::Relaxed));
+    serialize_with(id, as_string, serializer)
+}
+
+pub fn serialize_with<S: Serializer>(
+    id: &i32,
+    as_string: bool,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if as_string {
+        serializer.collect_str(id)
+    } else {
+        serializer.serialize_i32(*id)
+    }
+}
+
+// Accepts both shapes so clients can echo back whatever they were sent.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(i32),
+        String(String),
+    }
+
+    match Id::deserialize(deserializer)? {
+        Id::Number(id) => Ok(id),
+        Id::String(id) => id.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, value::Serializer};
+
+    #[test]
+    fn serialize_id_as_number_by_default() {
+        let value = serialize_with(&9007199, false, Serializer).unwrap();
+        assert_eq!(json!(9007199), value);
+    }
+
+    #[test]
+    fn serialize_id_as_string_when_enabled() {
+        let value = serialize_with(&9007199, true, Serializer).unwrap();
+        assert_eq!(json!("9007199"), value);
+    }
+
+    #[test]
+    fn deserialize_id_from_either_shape() {
+        assert_eq!(1, deserialize(json!(1)).unwrap());
+        assert_eq!(1, deserialize(json!("1")).unwrap());
+        assert!(deserialize(json!("one")).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use axum::{
+    body::{self, Full},
+    http::header,
+    response::Response,
+};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::convert::Infallible;
+use uuid::Uuid;
+
+use crate::util::config::Config;
+
+// Response extension for bodies that must reach the client exactly as the handler wrote them.
+#[derive(Debug, Clone, Copy)]
+pub struct Verbatim;
+
+// Only responses are rewritten; request bodies and `?fields=` keep the snake_case struct names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonStyle {
+    pub camel_case: bool,
+    pub pretty: bool,
+    pub envelope: bool,
+}
+
+impl JsonStyle {
+    // `None` when the handlers' compact snake_case output is already what was asked for.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let style = JsonStyle {
+            camel_case: config.json_camel_case,
+            pretty: config.json_pretty,
+            envelope: config.envelope_responses,
+        };
+        (style.camel_case || style.pretty || style.envelope).then_some(style)
+    }
+
+    pub async fn rewrite(self, res: Response) -> Result<Response, Infallible> {
+        let is_json = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("application/json"));
+        if !is_json || res.extensions().get::<Verbatim>().is_some() {
+            return Ok(res);
+        }
+
+        let (mut parts, body) = res.into_parts();
+        let bytes = match hyper::body::to_bytes(body).await {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                tracing::error!("cannot buffer JSON response: {}", err);
+                return Ok(Response::from_parts(parts, body::boxed(Full::default())));
+            }
+        };
+        // errors keep their own shape so clients can match on `error` without unwrapping
+        let wrap = self.envelope && parts.status.is_success();
+        let bytes = match serde_json::from_slice::<Value>(&bytes) {
+            Ok(value) => self.encode(value, wrap).unwrap_or_else(|_| bytes.to_vec()),
+            Err(_) => bytes.to_vec(),
+        };
+        parts.headers.remove(header::CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, body::boxed(Full::from(bytes))))
+    }
+
+    fn encode(self, value: Value, wrap: bool) -> serde_json::Result<Vec<u8>> {
+        let value = if wrap { envelope(value) } else { value };
+        let value = if self.camel_case { to_camel_case(value) } else { value };
+        if self.pretty {
+            serde_json::to_vec_pretty(&value)
+        } else {
+            serde_json::to_vec(&value)
+        }
+    }
+}
+
+// There is no request id upstream of the handlers, so each envelope mints its own.
+fn envelope(data: Value) -> Value {
+    serde_json::json!({
+        "data": data,
+        "meta": {
+            "request_id": Uuid::new_v4().to_string(),
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        },
+    })
+}
+
+pub fn to_camel_case(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (camel_case(&key), to_camel_case(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(to_camel_case).collect()),
+        value => value,
+    }
+}
+
+fn camel_case(key: &str) -> String {
+    let mut words = key.split('_');
+    let mut camel = words.next().unwrap_or_default().to_string();
+    for word in words {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rename_nested_keys() {
+        let value = json!([{ "updated_at": 1, "payload_json": { "todo_id": 2 }, "text": "keep_values" }]);
+        assert_eq!(
+            json!([{ "updatedAt": 1, "payloadJson": { "todoId": 2 }, "text": "keep_values" }]),
+            to_camel_case(value)
+        );
+    }
+
+    #[test]
+    fn skip_rewrite_unless_configured() {
+        assert_eq!(None, JsonStyle::from_config(&Config::default()));
+        let config = Config {
+            json_pretty: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            Some(JsonStyle { camel_case: false, pretty: true, envelope: false }),
+            JsonStyle::from_config(&config)
+        );
+    }
+}
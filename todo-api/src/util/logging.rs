@@ -0,0 +1,89 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    routing::Route,
+    Router,
+};
+use rand::Rng;
+use std::time::Instant;
+use tower::{layer::layer_fn, service_fn, ServiceExt};
+use tracing_subscriber::EnvFilter;
+
+// Used when `RUST_LOG` is unset or unparsable.
+const DEFAULT_DIRECTIVES: &str = "info";
+
+// Honors per-module directives such as `RUST_LOG=todo_api::repositories=debug,info`.
+pub fn init_tracing(directives: Option<&str>) {
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter(directives))
+        .init();
+}
+
+fn env_filter(directives: Option<&str>) -> EnvFilter {
+    directives
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| EnvFilter::new(DEFAULT_DIRECTIVES))
+}
+
+// One line per request. Only `sample_rate` of the successful ones are kept, while every 4xx and
+// 5xx is logged, so a quiet rate never hides a failure.
+pub fn log_requests(router: Router, sample_rate: f64) -> Router {
+    router.layer(layer_fn(move |inner: Route| {
+        service_fn(move |req: Request<Body>| {
+            let inner = inner.clone();
+            let method = req.method().clone();
+            let path = req.uri().path().to_string();
+            let started = Instant::now();
+            async move {
+                let res = inner.oneshot(req).await?;
+                if should_log(res.status(), sample_rate, &mut rand::thread_rng()) {
+                    tracing::info!("{} {} -> {} in {:?}", method, path, res.status().as_u16(), started.elapsed());
+                }
+                Ok(res)
+            }
+        })
+    }))
+}
+
+fn should_log<R: Rng>(status: StatusCode, sample_rate: f64, rng: &mut R) -> bool {
+    status.is_client_error() || status.is_server_error() || rng.gen::<f64>() < sample_rate
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn should_log_samples_successes_at_the_rate() {
+        let mut rng = StdRng::seed_from_u64(189);
+        let logged = |rate: f64, rng: &mut StdRng| (0..1000).filter(|_| should_log(StatusCode::OK, rate, rng)).count();
+
+        assert_eq!(0, logged(0.0, &mut rng));
+        assert_eq!(1000, logged(1.0, &mut rng));
+        let half = logged(0.5, &mut rng);
+        assert!((400..600).contains(&half), "{}", half);
+    }
+
+    #[test]
+    fn should_log_every_error_regardless_of_rate() {
+        let mut rng = StdRng::seed_from_u64(189);
+        for status in [StatusCode::NOT_FOUND, StatusCode::UNPROCESSABLE_ENTITY, StatusCode::INTERNAL_SERVER_ERROR] {
+            assert!(should_log(status, 0.0, &mut rng), "{}", status);
+        }
+    }
+
+    #[test]
+    fn env_filter_accepts_multiple_directives() {
+        let filter = env_filter(Some("todo_api::repositories=debug,tower_http=warn,info"));
+        let rendered = filter.to_string();
+        assert!(rendered.contains("todo_api::repositories=debug"), "{}", rendered);
+        assert!(rendered.contains("tower_http=warn"), "{}", rendered);
+    }
+
+    #[test]
+    fn env_filter_falls_back_on_invalid_directives() {
+        assert_eq!(DEFAULT_DIRECTIVES, env_filter(Some("todo_api=loud")).to_string());
+        assert_eq!(DEFAULT_DIRECTIVES, env_filter(None).to_string());
+    }
+}
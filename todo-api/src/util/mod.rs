@@ -1 +1,10 @@
-pub mod database;
\ No newline at end of file
+pub mod config;
+pub mod database;
+pub mod flexible_bool;
+pub mod id_format;
+pub mod json_style;
+pub mod logging;
+pub mod shutdown;
+pub mod text_len;
+pub mod tls;
+pub mod trailing_slash;
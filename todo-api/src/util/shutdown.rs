@@ -0,0 +1,87 @@
+use std::{future::Future, time::Duration};
+
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed install Ctrl+C handler.");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed install SIGTERM handler.")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown signal received, draining in-flight requests");
+}
+
+// Once `signal` fires, `serve` has `grace` to drain before it is dropped; `None` means it was cut off.
+pub async fn drain_within<S, F>(serve: S, signal: F, grace: Duration) -> Option<S::Output>
+where
+    S: Future,
+    F: Future<Output = ()>,
+{
+    tokio::pin!(serve);
+    tokio::select! {
+        output = &mut serve => return Some(output),
+        _ = signal => {},
+    }
+    tokio::time::timeout(grace, serve).await.ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::{routing::get, Router};
+    use futures::FutureExt;
+    use std::net::SocketAddr;
+    use tokio::sync::oneshot;
+
+    async fn drain_with_slow_handler(handler_secs: f64, grace: Duration) -> Option<hyper::Result<()>> {
+        let delay = Duration::from_secs_f64(handler_secs);
+        let app = Router::new().route("/slow", get(move || async move {
+            tokio::time::sleep(delay).await;
+            "done"
+        }));
+        let (tx, rx) = oneshot::channel::<()>();
+        let signal = rx.map(|_| ()).shared();
+        let server = axum::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .serve(app.into_make_service());
+        let addr = server.local_addr();
+        let server = server.with_graceful_shutdown(signal.clone());
+
+        tokio::spawn(async move {
+            let uri = format!("http://{}/slow", addr).parse().unwrap();
+            let _ = hyper::Client::new().get(uri).await;
+        });
+        tokio::spawn(async move {
+            // let the request reach the handler before shutting down
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            tx.send(()).unwrap();
+        });
+
+        drain_within(server, signal, grace).await
+    }
+
+    #[tokio::test]
+    async fn drain_finishes_within_grace() {
+        let result = drain_with_slow_handler(0.2, Duration::from_secs(5)).await;
+        assert!(matches!(result, Some(Ok(()))));
+    }
+
+    #[tokio::test]
+    async fn drain_is_cut_off_after_grace() {
+        let started = std::time::Instant::now();
+        let result = drain_with_slow_handler(10.0, Duration::from_millis(200)).await;
+        assert!(result.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
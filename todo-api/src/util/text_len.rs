@@ -0,0 +1,51 @@
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use validator::ValidationError;
+
+pub const DEFAULT_MAX_TEXT_LEN: usize = 100;
+
+// Set once at startup from `Config::max_todo_text_len`; `#[validate(length)]` only takes literals.
+static MAX_TEXT_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_TEXT_LEN);
+
+pub fn set_max_text_len(max: usize) {
+    MAX_TEXT_LEN.store(max, Ordering::Relaxed);
+}
+
+pub fn max_text_len() -> usize {
+    MAX_TEXT_LEN.load(Ordering::Relaxed)
+}
+
+pub fn validate(text: &str) -> Result<(), ValidationError> {
+    validate_with(text, max_text_len())
+}
+
+pub fn validate_with(text: &str, max: usize) -> Result<(), ValidationError> {
+    if text.chars().count() > max {
+        let mut error = ValidationError::new("length");
+        error.message = Some(Cow::from("Over text length"));
+        error.add_param(Cow::from("max"), &max);
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate_at_configured_boundary() {
+        for max in [5, 200] {
+            assert!(validate_with(&"a".repeat(max), max).is_ok(), "{}", max);
+            assert!(validate_with(&"a".repeat(max + 1), max).is_err(), "{}", max);
+        }
+    }
+
+    #[test]
+    fn validate_counts_chars_not_bytes() {
+        assert!(validate_with("ああああ", 4).is_ok());
+        assert!(validate_with("あああああ", 4).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Context;
+use axum_server::tls_rustls::RustlsConfig;
+
+use crate::util::config::Config;
+
+// TLS is enabled only when both paths are set; a half-configured pair is an error rather than a
+// silent fall back to plaintext.
+pub async fn from_config(config: &Config) -> anyhow::Result<Option<RustlsConfig>> {
+    match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => load(cert_path, key_path).await.map(Some),
+        (None, None) => Ok(None),
+        _ => anyhow::bail!("TLS_CERT_PATH and TLS_KEY_PATH must both be set"),
+    }
+}
+
+pub async fn load(cert_path: &str, key_path: &str) -> anyhow::Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(cert_path, key_path)
+        .await
+        .with_context(|| format!("failed to load TLS cert [{}] and key [{}]", cert_path, key_path))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn load_rejects_missing_files() {
+        let result = load("/nonexistent/cert.pem", "/nonexistent/key.pem").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn load_rejects_malformed_pem() {
+        let dir = std::env::temp_dir();
+        let cert_path = dir.join("todo-api-malformed-cert.pem");
+        let key_path = dir.join("todo-api-malformed-key.pem");
+        std::fs::write(&cert_path, "not a certificate").unwrap();
+        std::fs::write(&key_path, "not a key").unwrap();
+
+        let result = load(cert_path.to_str().unwrap(), key_path.to_str().unwrap()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn from_config_without_paths_is_plaintext() {
+        assert!(from_config(&Config::default()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn from_config_rejects_half_configured_pair() {
+        let cert_only = Config {
+            tls_cert_path: Some("/nonexistent/cert.pem".to_string()),
+            ..Config::default()
+        };
+        let key_only = Config {
+            tls_key_path: Some("/nonexistent/key.pem".to_string()),
+            ..Config::default()
+        };
+        for config in [cert_only, key_only] {
+            let error = from_config(&config).await.unwrap_err();
+            assert!(error.to_string().contains("must both be set"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use axum::http::{uri::PathAndQuery, Request, Uri};
+
+// axum routes `/todos/` and `/todos` separately, so the slash is dropped before routing: `/todos/?a=1`
+// becomes `/todos?a=1`. The root path is left alone.
+pub fn trim<B>(mut req: Request<B>) -> Request<B> {
+    let path = req.uri().path();
+    if path.len() <= 1 || !path.ends_with('/') {
+        return req;
+    }
+
+    let trimmed = match path.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    };
+    let path_and_query = match req.uri().query() {
+        Some(query) => format!("{}?{}", trimmed, query),
+        None => trimmed.to_string(),
+    };
+    let mut parts = req.uri().clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::try_from(path_and_query).expect("trimming keeps the path valid"));
+    *req.uri_mut() = Uri::from_parts(parts).expect("only the path changed");
+    req
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trimmed(uri: &str) -> String {
+        trim(Request::get(uri).body(()).unwrap()).uri().to_string()
+    }
+
+    #[test]
+    fn strips_trailing_slashes_and_keeps_the_query() {
+        assert_eq!("/todos", trimmed("/todos/"));
+        assert_eq!("/v1/todos/1", trimmed("/v1/todos/1//"));
+        assert_eq!("/todos?limit=1", trimmed("/todos/?limit=1"));
+        assert_eq!("/todos", trimmed("/todos"));
+        assert_eq!("/", trimmed("/"));
+        assert_eq!("/", trimmed("//"));
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Context;
+use axum::http::{header, Request, Uri};
+use hyper::{client::HttpConnector, Body, Client};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::repositories::Todo;
+
+const QUEUE_CAPACITY: usize = 1024;
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum TodoEvent {
+    Created { todo: Todo },
+    Updated { todo: Todo },
+    Deleted { id: i32 },
+}
+
+// Handlers only enqueue; a single background task owns delivery, so a slow or failing receiver
+// never holds up a request. Without `WEBHOOK_URL` every notification is dropped on the spot.
+#[derive(Debug, Clone, Default)]
+pub struct Webhooks {
+    queue: Option<mpsc::Sender<TodoEvent>>,
+}
+
+// Handle on the delivery task, kept by whoever owns shutdown so queued events still go out.
+#[derive(Debug, Default)]
+pub struct Dispatcher(Option<JoinHandle<()>>);
+
+impl Dispatcher {
+    // Only finishes once every `Webhooks` clone is gone, so call it after the server has stopped.
+    pub async fn drain(self, grace: Duration) {
+        if let Some(task) = self.0 {
+            if tokio::time::timeout(grace, task).await.is_err() {
+                tracing::warn!("webhook queue not drained after {:?}, dropping the rest", grace);
+            }
+        }
+    }
+}
+
+impl Webhooks {
+    // hyper's bare connector speaks plain HTTP only, so anything but an `http://` URL is refused
+    // here rather than failing every delivery later.
+    pub fn from_url(url: Option<&str>) -> anyhow::Result<(Self, Dispatcher)> {
+        match url {
+            Some(url) => {
+                let uri: Uri = url.parse().with_context(|| format!("{:?} is not a URL", url))?;
+                anyhow::ensure!(uri.scheme_str() == Some("http"), "{:?} is not an http:// URL", url);
+                anyhow::ensure!(uri.authority().is_some(), "{:?} has no host", url);
+                Ok(Webhooks::spawn(url.to_string(), INITIAL_BACKOFF))
+            }
+            None => Ok((Webhooks::default(), Dispatcher::default())),
+        }
+    }
+
+    fn spawn(url: String, initial_backoff: Duration) -> (Self, Dispatcher) {
+        let (queue, events) = mpsc::channel(QUEUE_CAPACITY);
+        let task = tokio::spawn(deliver(url, events, initial_backoff));
+        (Webhooks { queue: Some(queue) }, Dispatcher(Some(task)))
+    }
+
+    pub fn notify(&self, event: TodoEvent) {
+        if let Some(queue) = &self.queue {
+            if let Err(e) = queue.try_send(event) {
+                tracing::warn!("webhook queue rejected event: {}", e);
+            }
+        }
+    }
+}
+
+// Runs until every `Webhooks` clone is dropped, which happens once the server has shut down.
+async fn deliver(url: String, mut events: mpsc::Receiver<TodoEvent>, initial_backoff: Duration) {
+    let client = Client::new();
+    while let Some(event) = events.recv().await {
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("webhook event is not serializable: {}", e);
+                continue;
+            }
+        };
+        let mut backoff = initial_backoff;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match post(&client, &url, body.clone()).await {
+                Ok(()) => break,
+                Err(e) if attempt == MAX_ATTEMPTS => {
+                    tracing::error!("webhook gave up after {} attempts: {:#}", attempt, e)
+                }
+                Err(e) => {
+                    tracing::warn!("webhook attempt {} failed, retrying in {:?}: {:#}", attempt, backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+            }
+        }
+    }
+    tracing::debug!("webhook dispatcher stopped");
+}
+
+// Bounded so a receiver that accepts the connection but never answers can't stall the queue.
+async fn post(client: &Client<HttpConnector>, url: &str, body: Vec<u8>) -> anyhow::Result<()> {
+    let req = Request::post(url)
+        .header(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+        .body(Body::from(body))?;
+    let res = tokio::time::timeout(REQUEST_TIMEOUT, client.request(req))
+        .await
+        .with_context(|| format!("receiver did not answer within {:?}", REQUEST_TIMEOUT))??;
+    anyhow::ensure!(res.status().is_success(), "receiver answered {}", res.status());
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use axum::http::{Response, StatusCode};
+    use hyper::service::{make_service_fn, service_fn};
+    use std::{
+        convert::Infallible,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+
+    // Local receiver that answers 500 to the first `failures` requests and forwards every body it sees.
+    pub fn receiver(failures: usize) -> (String, mpsc::UnboundedReceiver<serde_json::Value>) {
+        let (sender, received) = mpsc::unbounded_channel();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let make_service = make_service_fn(move |_| {
+            let sender = sender.clone();
+            let seen = seen.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let sender = sender.clone();
+                    let seen = seen.clone();
+                    async move {
+                        let bytes = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        sender.send(serde_json::from_slice(&bytes).unwrap()).ok();
+                        let status = if seen.fetch_add(1, Ordering::SeqCst) < failures {
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        } else {
+                            StatusCode::NO_CONTENT
+                        };
+                        Ok::<_, Infallible>(Response::builder().status(status).body(Body::empty()).unwrap())
+                    }
+                }))
+            }
+        });
+        let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}/hook", server.local_addr());
+        tokio::spawn(server);
+        (url, received)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn retries_failed_delivery_with_backoff() {
+        let (url, mut received) = test_utils::receiver(2);
+        let (webhooks, _) = Webhooks::spawn(url, Duration::from_millis(10));
+
+        webhooks.notify(TodoEvent::Deleted { id: 7 });
+        let expected = serde_json::json!({ "event": "deleted", "id": 7 });
+        for _ in 0..3 {
+            let body = tokio::time::timeout(Duration::from_secs(5), received.recv())
+                .await
+                .expect("webhook was not retried")
+                .unwrap();
+            assert_eq!(expected, body);
+        }
+    }
+
+    #[tokio::test]
+    async fn drain_delivers_queued_events_after_senders_drop() {
+        let (url, mut received) = test_utils::receiver(0);
+        let (webhooks, dispatcher) = Webhooks::from_url(Some(&url)).unwrap();
+        for id in 1..=3 {
+            webhooks.notify(TodoEvent::Deleted { id });
+        }
+        drop(webhooks);
+        tokio::time::timeout(Duration::from_secs(5), dispatcher.drain(Duration::from_secs(5)))
+            .await
+            .expect("dispatcher did not stop");
+        for id in 1..=3 {
+            assert_eq!(serde_json::json!({ "event": "deleted", "id": id }), received.try_recv().unwrap());
+        }
+    }
+
+    #[test]
+    fn from_url_rejects_non_http_urls() {
+        for url in ["https://example.com/hook", "ftp://example.com", "not a url", "/hook"] {
+            assert!(Webhooks::from_url(Some(url)).is_err(), "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn notify_without_url_is_a_no_op() {
+        let (webhooks, dispatcher) = Webhooks::from_url(None).unwrap();
+        webhooks.notify(TodoEvent::Deleted { id: 1 });
+        dispatcher.drain(Duration::from_millis(10)).await;
+    }
+}